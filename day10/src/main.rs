@@ -1,22 +1,99 @@
+mod font;
+#[cfg(feature = "window")]
+mod viewer;
+
 use lazy_static::lazy_static;
-use piston_window::*;
 use regex::Regex;
 use std::io::prelude::*;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
 fn main() -> Result<()> {
+    let options = parse_args()?;
     let points = read_input()?;
 
-    show_points(&points);
+    if options.window {
+        return show_window(&points);
+    }
+
+    let seconds = find_message_time(&points);
+    let raster = Raster::at(&points, seconds);
+    part1(&raster);
+    part2(seconds);
+
+    if let Some(path) = &options.png_path {
+        raster.save_png(path)?;
+    }
 
     Ok(())
 }
 
+struct Options {
+    window: bool,
+    png_path: Option<String>,
+}
+
+fn parse_args() -> Result<Options> {
+    let mut options = Options {
+        window: false,
+        png_path: None,
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--window" => options.window = true,
+            "--png" => options.png_path = Some(args.next().ok_or("--png requires a path")?),
+            _ => return Err(format!("unknown argument: {}", arg).into()),
+        }
+    }
+
+    Ok(options)
+}
+
 fn read_input() -> Result<Vec<Point>> {
     let mut input = String::new();
     std::io::stdin().read_to_string(&mut input)?;
-    Ok(input.lines().map(parse_point).collect())
+    let points: Vec<Point> = input.lines().map(parse_point).collect();
+    if points.is_empty() {
+        return Err("no points in input".into());
+    }
+    Ok(points)
+}
+
+#[cfg(feature = "window")]
+fn show_window(points: &[Point]) -> Result<()> {
+    viewer::show_points(points);
+    Ok(())
+}
+
+#[cfg(not(feature = "window"))]
+fn show_window(_points: &[Point]) -> Result<()> {
+    Err("--window requires day10 to be built with the `window` feature".into())
+}
+
+fn part1(raster: &Raster) {
+    let glyphs = raster.glyphs();
+    let message: String = glyphs
+        .iter()
+        .map(|rows| font::recognise(rows).unwrap_or('?'))
+        .collect();
+
+    println!("part1: {}", message);
+    print!("{}", raster);
+
+    for (i, rows) in glyphs.iter().enumerate() {
+        if font::recognise(rows).is_none() {
+            println!("unknown glyph at position {}:", i + 1);
+            for row in rows {
+                println!("{}", row);
+            }
+        }
+    }
+}
+
+fn part2(seconds: i32) {
+    println!("part2: {}", seconds);
 }
 
 fn parse_point(s: &str) -> Point {
@@ -45,92 +122,170 @@ struct Point {
     velocity: Pair,
 }
 
+impl Point {
+    fn at(&self, seconds: i32) -> Pair {
+        Pair {
+            x: self.position.x + self.velocity.x * seconds,
+            y: self.position.y + self.velocity.y * seconds,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct Pair {
     x: i32,
     y: i32,
 }
 
-fn show_points(points: &[Point]) {
-    let mut points = Vec::from(points);
-    let width = 400;
-    let height = 400;
-    let mut seconds = 0;
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    min_x: i32,
+    min_y: i32,
+    max_x: i32,
+    max_y: i32,
+}
 
-    while points_out_of_bounds(&points, width, height) {
-        move_points(&mut points, true);
-        seconds += 1;
+impl Bounds {
+    fn at(points: &[Point], seconds: i32) -> Bounds {
+        let mut bounds = Bounds {
+            min_x: i32::MAX,
+            min_y: i32::MAX,
+            max_x: i32::MIN,
+            max_y: i32::MIN,
+        };
+        for p in points.iter().map(|p| p.at(seconds)) {
+            bounds.min_x = bounds.min_x.min(p.x);
+            bounds.min_y = bounds.min_y.min(p.y);
+            bounds.max_x = bounds.max_x.max(p.x);
+            bounds.max_y = bounds.max_y.max(p.y);
+        }
+        bounds
     }
 
-    println!("Points advanced to within a {}x{} block.", width, height);
-    println!("Press Left/Right to move points backwards/forwards in time until the text resolves.");
+    fn width(&self) -> usize {
+        (self.max_x - self.min_x) as usize + 1
+    }
 
-    let mut window: PistonWindow = WindowSettings::new(
-        format!("Advent of Code 2018 - Day 10: T={}", seconds),
-        [width as u32, height as u32],
-    )
-    .resizable(false)
-    .build()
-    .unwrap();
+    fn height(&self) -> usize {
+        (self.max_y - self.min_y) as usize + 1
+    }
 
-    let mut canvas = img::ImageBuffer::new(width as u32, height as u32);
+    fn area(&self) -> u64 {
+        self.width() as u64 * self.height() as u64
+    }
+}
 
-    let mut tex =
-        Texture::from_image(&mut window.factory, &canvas, &TextureSettings::new()).unwrap();
+fn find_message_time(points: &[Point]) -> i32 {
+    let area = |seconds| Bounds::at(points, seconds).area();
 
-    while let Some(e) = window.next() {
-        if let Some(button) = e.press_args() {
-            match button {
-                Button::Keyboard(Key::Space) | Button::Keyboard(Key::Right) => {
-                    move_points(&mut points, true);
-                    seconds += 1;
-                }
-                Button::Keyboard(Key::Backspace) | Button::Keyboard(Key::Left) => {
-                    move_points(&mut points, false);
-                    seconds -= 1;
-                }
-                _ => {}
-            }
-            window.set_title(format!("Advent of Code 2018 - Day 10: T={}", seconds));
-        }
-        if let Some(_) = e.render_args() {
-            draw_points(&mut canvas, &points);
-            tex.update(&mut window.encoder, &canvas).unwrap();
-            window.draw_2d(&e, |c, g| {
-                clear([0.0, 0.0, 0.0, 1.0], g);
-                image(&tex, c.transform, g);
-            });
+    // The points converge on the message and then drift apart again, so the bounding box area
+    // shrinks to a single minimum and grows afterwards. Double the search range until the area
+    // starts growing and then narrow it down with a ternary search.
+    let mut hi = 1;
+    while area(hi * 2) < area(hi) {
+        hi *= 2;
+    }
+    hi *= 2;
+
+    let mut lo = 0;
+    while hi - lo > 2 {
+        let m1 = lo + (hi - lo) / 3;
+        let m2 = hi - (hi - lo) / 3;
+        if area(m1) < area(m2) {
+            hi = m2;
+        } else {
+            lo = m1;
         }
     }
+
+    (lo..=hi).min_by_key(|&seconds| area(seconds)).unwrap()
 }
 
-fn points_out_of_bounds(points: &[Point], width: i32, height: i32) -> bool {
-    points.iter().any(|p| {
-        p.position.x < 0 || p.position.x >= width || p.position.y < 0 || p.position.y >= height
-    })
+struct Raster {
+    width: usize,
+    height: usize,
+    cells: Vec<bool>,
 }
 
-fn draw_points(canvas: &mut img::ImageBuffer<img::Rgba<u8>, Vec<u8>>, points: &[Point]) {
-    let black = img::Rgba([0, 0, 0, 255]);
-    let white = img::Rgba([255, 255, 255, 255]);
+impl Raster {
+    fn at(points: &[Point], seconds: i32) -> Raster {
+        let bounds = Bounds::at(points, seconds);
+        let width = bounds.width();
+        let height = bounds.height();
+        let mut cells = vec![false; width * height];
 
-    canvas.pixels_mut().for_each(|p| *p = black);
+        for p in points.iter().map(|p| p.at(seconds)) {
+            let x = (p.x - bounds.min_x) as usize;
+            let y = (p.y - bounds.min_y) as usize;
+            cells[y * width + x] = true;
+        }
 
-    for point in points {
-        if point.position.x >= 0
-            && (point.position.x as u32) < canvas.width()
-            && point.position.y >= 0
-            && (point.position.y as u32) < canvas.height()
-        {
-            canvas.put_pixel(point.position.x as u32, point.position.y as u32, white);
+        Raster {
+            width,
+            height,
+            cells,
         }
     }
+
+    fn lit(&self, x: usize, y: usize) -> bool {
+        self.cells[y * self.width + x]
+    }
+
+    fn save_png(&self, path: &str) -> Result<()> {
+        const SCALE: usize = 4;
+        let canvas = img::ImageBuffer::from_fn(
+            (self.width * SCALE) as u32,
+            (self.height * SCALE) as u32,
+            |x, y| {
+                if self.lit(x as usize / SCALE, y as usize / SCALE) {
+                    img::Luma([255u8])
+                } else {
+                    img::Luma([0u8])
+                }
+            },
+        );
+        canvas.save(path)?;
+        Ok(())
+    }
+
+    fn column_empty(&self, x: usize) -> bool {
+        (0..self.height).all(|y| !self.lit(x, y))
+    }
+
+    /// Splits the raster into glyphs separated by empty columns, each as rows of `#`/`.`.
+    fn glyphs(&self) -> Vec<Vec<String>> {
+        let mut result = Vec::new();
+        let mut x = 0;
+
+        while x < self.width {
+            if self.column_empty(x) {
+                x += 1;
+                continue;
+            }
+            let start = x;
+            while x < self.width && !self.column_empty(x) {
+                x += 1;
+            }
+            let rows = (0..self.height)
+                .map(|y| {
+                    (start..x)
+                        .map(|x| if self.lit(x, y) { '#' } else { '.' })
+                        .collect()
+                })
+                .collect();
+            result.push(rows);
+        }
+
+        result
+    }
 }
 
-fn move_points(points: &mut [Point], forward: bool) {
-    let sign = if forward { 1 } else { -1 };
-    points.iter_mut().for_each(|p| {
-        p.position.x += p.velocity.x * sign;
-        p.position.y += p.velocity.y * sign;
-    });
+impl std::fmt::Display for Raster {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.cells.chunks(self.width) {
+            let line: String = row.iter().map(|&lit| if lit { '#' } else { '.' }).collect();
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
 }
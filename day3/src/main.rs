@@ -7,14 +7,40 @@ use shared::Grid;
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
 fn main() -> Result<()> {
+    let options = parse_args()?;
     let claims = read_input()?;
 
     part1(&claims);
-    part2(&claims);
+    let intact = part2(&claims);
+
+    if let Some(path) = &options.heatmap {
+        export_heatmap(&claims, intact, path)?;
+        println!("heatmap written to {}", path);
+    }
 
     Ok(())
 }
 
+struct Options {
+    heatmap: Option<String>,
+}
+
+fn parse_args() -> Result<Options> {
+    let mut options = Options { heatmap: None };
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--heatmap" => {
+                options.heatmap = Some(args.next().ok_or("--heatmap requires a file path")?);
+            }
+            _ => return Err(format!("unknown argument: {}", arg).into()),
+        }
+    }
+
+    Ok(options)
+}
+
 struct Claim {
     pub id: usize,
     pub left: usize,
@@ -46,6 +72,14 @@ fn parse_claim(s: &str) -> Claim {
 }
 
 fn part1(claims: &[Claim]) {
+    let grid = coverage(claims);
+
+    let num_overlaps = grid.cells.iter().filter(|c| **c >= 2).count();
+
+    println!("part1: {}", num_overlaps);
+}
+
+fn coverage(claims: &[Claim]) -> Grid<usize> {
     let mut grid = Grid::new(1000, 0);
 
     for claim in claims {
@@ -56,12 +90,10 @@ fn part1(claims: &[Claim]) {
         }
     }
 
-    let num_overlaps = grid.cells.iter().filter(|c| **c >= 2).count();
-
-    println!("part1: {}", num_overlaps);
+    grid
 }
 
-fn part2(claims: &[Claim]) {
+fn part2(claims: &[Claim]) -> Option<&Claim> {
     let mut grid: Grid<Vec<usize>> = Grid::new(1000, Vec::new());
 
     for claim in claims {
@@ -85,7 +117,78 @@ fn part2(claims: &[Claim]) {
     for claim in claims {
         if !overlaps.contains_key(&claim.id) {
             println!("part2: {}", claim.id);
-            return;
+            return Some(claim);
         }
     }
+
+    None
+}
+
+fn export_heatmap(claims: &[Claim], intact: Option<&Claim>, path: &str) -> Result<()> {
+    let grid = coverage(claims);
+    let max_depth = grid.cells.iter().cloned().max().unwrap_or(0);
+    let size = grid.size() as u32;
+
+    let mut canvas = img::ImageBuffer::from_fn(size, size, |x, y| {
+        heat_colour(grid[(x as usize, y as usize)], max_depth)
+    });
+
+    if let Some(claim) = intact {
+        outline_claim(&mut canvas, claim, img::Rgb([0, 255, 0]));
+    }
+
+    canvas.save(path)?;
+
+    Ok(())
+}
+
+fn heat_colour(depth: usize, max_depth: usize) -> img::Rgb<u8> {
+    const RAMP: [[u8; 3]; 5] = [
+        [0, 0, 96],
+        [0, 128, 255],
+        [255, 255, 0],
+        [255, 128, 0],
+        [255, 0, 0],
+    ];
+
+    if depth == 0 {
+        return img::Rgb([0, 0, 0]);
+    }
+    if max_depth <= 1 {
+        return img::Rgb(RAMP[0]);
+    }
+
+    // scale depth 1..=max_depth onto the ramp and interpolate between adjacent stops
+    let t = (depth - 1) as f64 / (max_depth - 1) as f64 * (RAMP.len() - 1) as f64;
+    let i = (t.floor() as usize).min(RAMP.len() - 2);
+    let frac = t - i as f64;
+    let (a, b) = (RAMP[i], RAMP[i + 1]);
+    let lerp = |c: usize| (a[c] as f64 + (b[c] as f64 - a[c] as f64) * frac).round() as u8;
+
+    img::Rgb([lerp(0), lerp(1), lerp(2)])
+}
+
+fn outline_claim(
+    canvas: &mut img::ImageBuffer<img::Rgb<u8>, Vec<u8>>,
+    claim: &Claim,
+    colour: img::Rgb<u8>,
+) {
+    let (left, top) = (claim.left as u32, claim.top as u32);
+    let right = (claim.left + claim.width) as u32;
+    let bottom = (claim.top + claim.height) as u32;
+
+    // draw just outside the claim so the claim itself stays visible
+    let x0 = left.saturating_sub(1);
+    let y0 = top.saturating_sub(1);
+    let x1 = right.min(canvas.width() - 1);
+    let y1 = bottom.min(canvas.height() - 1);
+
+    for x in x0..=x1 {
+        canvas.put_pixel(x, y0, colour);
+        canvas.put_pixel(x, y1, colour);
+    }
+    for y in y0..=y1 {
+        canvas.put_pixel(x0, y, colour);
+        canvas.put_pixel(x1, y, colour);
+    }
 }
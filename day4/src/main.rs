@@ -7,23 +7,95 @@ use std::collections::HashMap;
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
 fn main() -> Result<()> {
+    let options = parse_args()?;
     let events = read_input()?;
 
-    part1(&events);
-    part2(&events);
+    let anomalies = validate(&events);
+    for anomaly in &anomalies {
+        eprintln!("{}", anomaly);
+    }
+    if options.strict && !anomalies.is_empty() {
+        return Err(format!("refusing to compute answers: {} anomalies in log", anomalies.len()).into());
+    }
+
+    part1(&events, &options.window);
+    part2(&events, &options.window);
+
+    match options.report {
+        Some(ReportFormat::Text) => print_report(&events, &options.window),
+        Some(ReportFormat::Csv) => print_report_csv(&events, &options.window),
+        None => {}
+    }
 
     Ok(())
 }
 
+struct Options {
+    window: Window,
+    strict: bool,
+    report: Option<ReportFormat>,
+}
+
+enum ReportFormat {
+    Text,
+    Csv,
+}
+
+fn parse_args() -> Result<Options> {
+    let mut options = Options {
+        window: Window::default(),
+        strict: false,
+        report: None,
+    };
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--window-start" => {
+                let value = args.next().ok_or("--window-start requires HH:MM")?;
+                options.window.start = parse_time_of_day(&value)?;
+            }
+            "--window-len" => {
+                let value = args.next().ok_or("--window-len requires a number of minutes")?;
+                let len: u16 = value.parse()?;
+                if len == 0 || len > MINUTES_PER_DAY {
+                    return Err(format!("window length must be 1..={} minutes", MINUTES_PER_DAY).into());
+                }
+                options.window.len = len;
+            }
+            "--strict" => options.strict = true,
+            "--report" => options.report = Some(ReportFormat::Text),
+            "--report-csv" => options.report = Some(ReportFormat::Csv),
+            _ => return Err(format!("unknown argument: {}", arg).into()),
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_time_of_day(s: &str) -> Result<u16> {
+    let mut parts = s.splitn(2, ':');
+    let hour: u16 = parts.next().unwrap_or("").parse()?;
+    let minute: u16 = parts.next().ok_or("expected HH:MM")?.parse()?;
+    if hour >= 24 || minute >= 60 {
+        return Err(format!("invalid time of day: {}", s).into());
+    }
+    Ok(hour * 60 + minute)
+}
+
 fn read_input() -> Result<Vec<Event>> {
     let mut input = String::new();
     std::io::stdin().read_to_string(&mut input)?;
-    let mut events: Vec<Event> = input.lines().map(|s| parse_event(s)).collect();
+    let mut events: Vec<Event> = input
+        .lines()
+        .enumerate()
+        .map(|(i, s)| parse_event(i + 1, s))
+        .collect();
     events.sort();
     Ok(events)
 }
 
-fn parse_event(s: &str) -> Event {
+fn parse_event(line: usize, s: &str) -> Event {
     lazy_static! {
         static ref time_re: Regex = Regex::new(r"\[(\d{4})-(\d{2})-(\d{2}) (\d{2}):(\d{2})\]").unwrap();
         static ref begins_shift_re: Regex = Regex::new(r"Guard #(\d+) begins shift").unwrap();
@@ -48,6 +120,7 @@ fn parse_event(s: &str) -> Event {
     };
 
     Event {
+        line,
         time,
         activity,
     }
@@ -62,15 +135,148 @@ struct Time {
     minute: u8,
 }
 
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
 impl Time {
     fn sub(&self, other: &Time) -> usize {
-        // we can ignore all fields except minute
-        (self.minute - other.minute) as usize
+        (self.to_minutes() - other.to_minutes()) as usize
+    }
+
+    /// Minutes since 0001-01-01 00:00 in the proleptic Gregorian calendar.
+    fn to_minutes(&self) -> i64 {
+        let y = i64::from(self.year) - 1;
+        let mut days = y * 365 + y / 4 - y / 100 + y / 400;
+        for month in 1..self.month {
+            days += i64::from(days_in_month(self.year, month));
+        }
+        days += i64::from(self.day) - 1;
+
+        days * i64::from(MINUTES_PER_DAY) + i64::from(self.minute_of_day())
+    }
+
+    fn from_minutes(minutes: i64) -> Time {
+        const DAYS_PER_400_YEARS: i64 = 146_097;
+
+        let mut days = minutes.div_euclid(i64::from(MINUTES_PER_DAY));
+        let minute_of_day = minutes.rem_euclid(i64::from(MINUTES_PER_DAY)) as u16;
+
+        let mut year = 1 + 400 * (days / DAYS_PER_400_YEARS) as u16;
+        days %= DAYS_PER_400_YEARS;
+        while days >= days_in_year(year) {
+            days -= days_in_year(year);
+            year += 1;
+        }
+
+        let mut month = 1;
+        while days >= i64::from(days_in_month(year, month)) {
+            days -= i64::from(days_in_month(year, month));
+            month += 1;
+        }
+
+        Time {
+            year,
+            month,
+            day: days as u16 + 1,
+            hour: (minute_of_day / 60) as u8,
+            minute: (minute_of_day % 60) as u8,
+        }
+    }
+
+    fn add_minutes(&self, minutes: i64) -> Time {
+        Time::from_minutes(self.to_minutes() + minutes)
+    }
+
+    fn minute_of_day(&self) -> u16 {
+        u16::from(self.hour) * 60 + u16::from(self.minute)
+    }
+
+    fn next_minute(&self) -> Time {
+        let mut t = self.clone();
+        t.minute += 1;
+        if t.minute == 60 {
+            t.minute = 0;
+            t.hour += 1;
+        }
+        if t.hour == 24 {
+            t.hour = 0;
+            t.day += 1;
+        }
+        if t.day > days_in_month(t.year, t.month) {
+            t.day = 1;
+            t.month += 1;
+        }
+        if t.month > 12 {
+            t.month = 1;
+            t.year += 1;
+        }
+        t
+    }
+}
+
+fn is_leap_year(year: u16) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_year(year: u16) -> i64 {
+    if is_leap_year(year) {
+        366
+    } else {
+        365
+    }
+}
+
+fn days_in_month(year: u16, month: u16) -> u16 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The span of each day whose minutes are tallied per guard, by default the midnight hour.
+#[derive(Debug, Clone)]
+struct Window {
+    start: u16,
+    len: u16,
+}
+
+impl Default for Window {
+    fn default() -> Window {
+        Window { start: 0, len: 60 }
+    }
+}
+
+impl Window {
+    fn offset(&self, time: &Time) -> Option<u16> {
+        let offset = (time.minute_of_day() + MINUTES_PER_DAY - self.start) % MINUTES_PER_DAY;
+        if offset < self.len {
+            Some(offset)
+        } else {
+            None
+        }
+    }
+
+    /// The minute of the hour at the given offset into the window, as used in the answers.
+    fn minute(&self, offset: u16) -> u16 {
+        (self.start + offset) % 60
+    }
+
+    /// The start of the window a shift beginning at `time` is watching: the current one if the
+    /// shift begins inside a window, otherwise the next one.
+    fn night_of(&self, time: &Time) -> Time {
+        let offset = (time.minute_of_day() + MINUTES_PER_DAY - self.start) % MINUTES_PER_DAY;
+        if offset < self.len {
+            time.add_minutes(-i64::from(offset))
+        } else {
+            time.add_minutes(i64::from(MINUTES_PER_DAY - offset))
+        }
     }
 }
 
 #[derive(Debug, Eq)]
 struct Event {
+    line: usize,
     time: Time,
     activity: Activity,
 }
@@ -100,20 +306,134 @@ enum Activity {
     WakesUp,
 }
 
-fn part1(events: &[Event]) {
-    let guards = collate_guard_data(events);
-    let most_asleep_guard = guards.values().max_by_key(|g| g.total_asleep).unwrap();
-    let (most_asleep_min, _) = most_asleep_guard.asleep.iter().max_by_key(|(_, count)| *count).unwrap();
+fn part1(events: &[Event], window: &Window) {
+    let guards = collate_guard_data(events, window);
+    let most_asleep_guard = match guards.values().max_by_key(|g| g.total_asleep) {
+        Some(guard) => guard,
+        None => {
+            println!("part1: no guards on duty");
+            return;
+        }
+    };
+    let (most_asleep_offset, _) = match most_asleep_guard.asleep.iter().max_by_key(|(_, count)| *count) {
+        Some(entry) => entry,
+        None => {
+            println!("part1: guard #{} never slept within the window", most_asleep_guard.id);
+            return;
+        }
+    };
 
-    let result = most_asleep_guard.id * (*most_asleep_min as usize);
+    let result = most_asleep_guard.id * (window.minute(*most_asleep_offset) as usize);
 
     println!("part1: {}", result);
 }
 
-fn collate_guard_data(events: &[Event]) -> HashMap<usize,Guard> {
+#[derive(Debug)]
+enum Anomaly {
+    BeforeAnyShift { line: usize },
+    DuplicateTimestamp { line: usize, other_line: usize },
+    DoubleSleep { line: usize, asleep_since_line: usize },
+    WakeWithoutSleep { line: usize },
+    AsleepAtShiftChange { line: usize, guard: usize, asleep_since_line: usize },
+    AsleepAtEndOfLog { guard: usize, asleep_since_line: usize },
+}
+
+impl std::fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Anomaly::BeforeAnyShift { line } => {
+                write!(f, "line {}: event before any guard began a shift", line)
+            }
+            Anomaly::DuplicateTimestamp { line, other_line } => {
+                write!(f, "line {}: same timestamp as line {}", line, other_line)
+            }
+            Anomaly::DoubleSleep { line, asleep_since_line } => write!(
+                f,
+                "line {}: falls asleep while already asleep since line {}",
+                line, asleep_since_line
+            ),
+            Anomaly::WakeWithoutSleep { line } => {
+                write!(f, "line {}: wakes up without having fallen asleep", line)
+            }
+            Anomaly::AsleepAtShiftChange { line, guard, asleep_since_line } => write!(
+                f,
+                "line {}: shift changes while guard #{} is still asleep since line {}",
+                line, guard, asleep_since_line
+            ),
+            Anomaly::AsleepAtEndOfLog { guard, asleep_since_line } => write!(
+                f,
+                "end of log: guard #{} is still asleep since line {}",
+                guard, asleep_since_line
+            ),
+        }
+    }
+}
+
+fn validate(events: &[Event]) -> Vec<Anomaly> {
+    let mut anomalies = Vec::new();
+    let mut current_guard = None;
+    let mut asleep_since: Option<&Event> = None;
+    let mut prev: Option<&Event> = None;
+
+    for event in events {
+        // Event equality only compares timestamps, so this catches entries sorting would interleave
+        if let Some(prev) = prev {
+            if prev == event {
+                anomalies.push(Anomaly::DuplicateTimestamp {
+                    line: event.line,
+                    other_line: prev.line,
+                });
+            }
+        }
+        prev = Some(event);
+
+        match event.activity {
+            Activity::BeginShift(id) => {
+                if let (Some(guard), Some(since)) = (current_guard, asleep_since) {
+                    anomalies.push(Anomaly::AsleepAtShiftChange {
+                        line: event.line,
+                        guard,
+                        asleep_since_line: since.line,
+                    });
+                }
+                current_guard = Some(id);
+                asleep_since = None;
+            }
+            _ if current_guard.is_none() => {
+                anomalies.push(Anomaly::BeforeAnyShift { line: event.line });
+            }
+            Activity::FallsAsleep => {
+                if let Some(since) = asleep_since {
+                    anomalies.push(Anomaly::DoubleSleep {
+                        line: event.line,
+                        asleep_since_line: since.line,
+                    });
+                } else {
+                    asleep_since = Some(event);
+                }
+            }
+            Activity::WakesUp => {
+                if asleep_since.take().is_none() {
+                    anomalies.push(Anomaly::WakeWithoutSleep { line: event.line });
+                }
+            }
+        }
+    }
+
+    if let (Some(guard), Some(since)) = (current_guard, asleep_since) {
+        anomalies.push(Anomaly::AsleepAtEndOfLog {
+            guard,
+            asleep_since_line: since.line,
+        });
+    }
+
+    anomalies
+}
+
+fn collate_guard_data(events: &[Event], window: &Window) -> HashMap<usize,Guard> {
     let mut guards: HashMap<usize,Guard> = HashMap::new();
     let mut current_guard = None;
-    let mut sleep_start = Time::default();
+    let mut sleep_start: Option<Time> = None;
 
     for event in events {
         match event.activity {
@@ -123,19 +443,44 @@ fn collate_guard_data(events: &[Event]) -> HashMap<usize,Guard> {
                         id,
                         total_asleep: 0,
                         asleep: HashMap::new(),
+                        nights: Vec::new(),
                     });
                 }
+                guards.get_mut(&id).unwrap().nights.push(Night {
+                    start: window.night_of(&event.time),
+                    asleep: vec![false; window.len as usize],
+                });
                 current_guard = Some(id);
+                // an unfinished nap from the previous shift is discarded
+                sleep_start = None;
             },
             Activity::FallsAsleep => {
-                sleep_start = event.time.clone();
+                if sleep_start.is_none() {
+                    sleep_start = Some(event.time.clone());
+                }
             },
             Activity::WakesUp => {
+                let (id, sleep_start) = match (current_guard, sleep_start.take()) {
+                    (Some(id), Some(start)) => (id, start),
+                    _ => continue,
+                };
                 let sleep_len = event.time.sub(&sleep_start);
-                if let Some(guard) = guards.get_mut(&current_guard.unwrap()) {
-                    guard.total_asleep += sleep_len as usize;
-                    for min in sleep_start.minute..event.time.minute {
-                        *guard.asleep.entry(min).or_insert(0) += 1;
+                if let Some(guard) = guards.get_mut(&id) {
+                    guard.total_asleep += sleep_len;
+                    let mut t = sleep_start.clone();
+                    while t < event.time {
+                        if let Some(min) = window.offset(&t) {
+                            *guard.asleep.entry(min).or_insert(0) += 1;
+                        }
+                        if let Some(night) = guard.nights.last_mut() {
+                            if t >= night.start {
+                                let offset = t.sub(&night.start);
+                                if offset < night.asleep.len() {
+                                    night.asleep[offset] = true;
+                                }
+                            }
+                        }
+                        t = t.next_minute();
                     }
                 }
             }
@@ -149,12 +494,92 @@ fn collate_guard_data(events: &[Event]) -> HashMap<usize,Guard> {
 struct Guard {
     id: usize,
     total_asleep: usize,
-    asleep: HashMap<u8,usize>,
+    asleep: HashMap<u16,usize>,
+    nights: Vec<Night>,
 }
 
-fn part2(events: &[Event]) {
-    let guards = collate_guard_data(events);
-    let mut minutes: HashMap<u8, GuardSleepCount> = HashMap::new();
+#[derive(Debug)]
+struct Night {
+    start: Time,
+    asleep: Vec<bool>,
+}
+
+fn sorted_guards(events: &[Event], window: &Window) -> Vec<Guard> {
+    let mut guards: Vec<Guard> = collate_guard_data(events, window).into_values().collect();
+    guards.sort_by_key(|g| g.id);
+    guards
+}
+
+fn print_report(events: &[Event], window: &Window) {
+    let len = window.len as usize;
+    let tens: String = (0..len).map(|m| digit(m / 10 % 10)).collect();
+    let ones: String = (0..len).map(|m| digit(m % 10)).collect();
+
+    let guards = sorted_guards(events, window);
+    let column = guards
+        .iter()
+        .map(|guard| format!("#{}", guard.id).len())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+
+    for guard in guards {
+        println!();
+        println!("Guard #{}: {} minutes asleep", guard.id, guard.total_asleep);
+
+        let max_count = guard.asleep.values().cloned().max().unwrap_or(0);
+        for level in (1..=max_count).rev() {
+            let row: String = (0..window.len)
+                .map(|m| if guard.asleep.get(&m).cloned().unwrap_or(0) >= level { '#' } else { ' ' })
+                .collect();
+            println!("{:>5}  {:>w$}  {}", "", level, row.trim_end(), w = column);
+        }
+
+        println!("{:<5}  {:<w$}  {}", "Date", "", tens, w = column);
+        println!("{:<5}  {:<w$}  {}", "", "", ones, w = column);
+        let id = format!("#{}", guard.id);
+        for night in &guard.nights {
+            let timeline: String = night.asleep.iter().map(|&a| if a { '#' } else { '.' }).collect();
+            println!(
+                "{:02}-{:02}  {:<w$}  {}",
+                night.start.month, night.start.day, id, timeline, w = column
+            );
+        }
+    }
+}
+
+fn print_report_csv(events: &[Event], window: &Window) {
+    let header: Vec<String> = (0..window.len).map(|m| format!("m{}", m)).collect();
+    println!("guard,date,asleep,{}", header.join(","));
+
+    for guard in sorted_guards(events, window) {
+        let histogram: Vec<String> = (0..window.len)
+            .map(|m| guard.asleep.get(&m).cloned().unwrap_or(0).to_string())
+            .collect();
+        println!("{},all,{},{}", guard.id, guard.total_asleep, histogram.join(","));
+
+        for night in &guard.nights {
+            let minutes: Vec<&str> = night.asleep.iter().map(|&a| if a { "1" } else { "0" }).collect();
+            println!(
+                "{},{:04}-{:02}-{:02},{},{}",
+                guard.id,
+                night.start.year,
+                night.start.month,
+                night.start.day,
+                night.asleep.iter().filter(|&&a| a).count(),
+                minutes.join(",")
+            );
+        }
+    }
+}
+
+fn digit(n: usize) -> char {
+    (b'0' + n as u8) as char
+}
+
+fn part2(events: &[Event], window: &Window) {
+    let guards = collate_guard_data(events, window);
+    let mut minutes: HashMap<u16, GuardSleepCount> = HashMap::new();
 
     for guard in guards.values() {
         for (min, count) in &guard.asleep {
@@ -166,8 +591,14 @@ fn part2(events: &[Event]) {
         }
     }
 
-    let (min, guard) = minutes.iter().max_by_key(|(_, guard)| guard.count).unwrap();
-    let result = guard.id * (*min as usize);
+    let (offset, guard) = match minutes.iter().max_by_key(|(_, guard)| guard.count) {
+        Some(entry) => entry,
+        None => {
+            println!("part2: no guard slept within the window");
+            return;
+        }
+    };
+    let result = guard.id * (window.minute(*offset) as usize);
 
     println!("part2: {}", result);
 }
@@ -1,18 +1,91 @@
 use rayon::prelude::*;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap};
 use std::io::prelude::*;
-use std::iter::FromIterator;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
 fn main() -> Result<()> {
+    let options = parse_args()?;
     let polymer = read_input()?;
 
-    part1(&polymer);
-    part2(&polymer);
+    let reduced = part1(&polymer, &options.rule, options.print_polymer);
+    part2(&polymer, &reduced, &options.rule);
 
     Ok(())
 }
 
+struct Options {
+    rule: Rule,
+    print_polymer: bool,
+}
+
+fn parse_args() -> Result<Options> {
+    let mut options = Options {
+        rule: Rule::CasePair,
+        print_polymer: false,
+    };
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--print" => options.print_polymer = true,
+            "--rule" => {
+                let value = args.next().ok_or("--rule requires case, fold or pairs=<pairs>")?;
+                options.rule = parse_rule(&value)?;
+            }
+            _ => return Err(format!("unknown argument: {}", arg).into()),
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_rule(s: &str) -> Result<Rule> {
+    match s {
+        "case" => Ok(Rule::CasePair),
+        "fold" => Ok(Rule::CaseFold),
+        _ if s.starts_with("pairs=") => {
+            let mut table = HashMap::new();
+            for pair in s["pairs=".len()..].split(',') {
+                let units: Vec<char> = pair.chars().collect();
+                if units.len() != 2 || units[0] == units[1] {
+                    return Err(format!("invalid reaction pair: {:?}", pair).into());
+                }
+                table.entry(units[0]).or_insert_with(Vec::new).push(units[1]);
+                table.entry(units[1]).or_insert_with(Vec::new).push(units[0]);
+            }
+            let types = pair_components(&table);
+            Ok(Rule::PairTable(table, types))
+        }
+        _ => Err(format!("unknown rule: {}", s).into()),
+    }
+}
+
+/// Maps every unit in the table to the smallest unit it can be linked to through reaction pairs.
+fn pair_components(table: &HashMap<char, Vec<char>>) -> HashMap<char, char> {
+    let units: BTreeSet<char> = table.keys().cloned().collect();
+    let mut types = HashMap::new();
+
+    for &start in &units {
+        if types.contains_key(&start) {
+            continue;
+        }
+        types.insert(start, start);
+        let mut stack = vec![start];
+        while let Some(unit) = stack.pop() {
+            for &partner in &table[&unit] {
+                if let Entry::Vacant(entry) = types.entry(partner) {
+                    entry.insert(start);
+                    stack.push(partner);
+                }
+            }
+        }
+    }
+
+    types
+}
+
 fn read_input() -> Result<Vec<char>> {
     let mut input = String::new();
     std::io::stdin().read_to_string(&mut input)?;
@@ -20,56 +93,96 @@ fn read_input() -> Result<Vec<char>> {
     Ok(input.chars().collect())
 }
 
-fn part1(polymer: &[char]) {
-    let result = react(Vec::from(polymer));
+enum Rule {
+    /// ASCII letters react with the same letter in the opposite case.
+    CasePair,
+    /// Any two distinct units with the same Unicode lowercase form react.
+    CaseFold,
+    /// Only the listed pairs react, in either order. Units linked by pairs share a unit type.
+    PairTable(HashMap<char, Vec<char>>, HashMap<char, char>),
+}
+
+impl Rule {
+    fn reacts(&self, a: char, b: char) -> bool {
+        match self {
+            Rule::CasePair => a != b && a.eq_ignore_ascii_case(&b),
+            Rule::CaseFold => a != b && a.to_lowercase().eq(b.to_lowercase()),
+            Rule::PairTable(table, _) => table.get(&a).is_some_and(|partners| partners.contains(&b)),
+        }
+    }
+
+    /// Units of the same type are removed together in part 2.
+    fn unit_type(&self, unit: char) -> char {
+        match self {
+            Rule::CasePair => unit.to_ascii_lowercase(),
+            Rule::CaseFold => {
+                let mut lower = unit.to_lowercase();
+                match (lower.next(), lower.next()) {
+                    (Some(c), None) => c,
+                    _ => unit,
+                }
+            }
+            Rule::PairTable(_, types) => types.get(&unit).cloned().unwrap_or(unit),
+        }
+    }
+}
+
+fn part1(polymer: &[char], rule: &Rule, print_polymer: bool) -> Vec<char> {
+    let result = react(polymer.iter().cloned(), rule);
 
-    println!("{}", String::from_iter(result.iter()));
+    if print_polymer {
+        println!("{}", result.iter().collect::<String>());
+    }
     println!("part1: {}", result.len());
+
+    result
 }
 
-fn react(mut input: Vec<char>) -> Vec<char> {
+fn react<I>(input: I, rule: &Rule) -> Vec<char>
+where
+    I: IntoIterator<Item = char>,
+{
     let mut result: Vec<char> = Vec::new();
 
-    loop {
-        if input.len() == 0 {
-            break;
-        } else if input.len() == 1 {
-            result.push(input.remove(0));
-            break;
-        }
-
-        let a = input.remove(0);
-        let b = input[0];
-        if a.is_lowercase() != b.is_lowercase()
-            && a.to_lowercase().next() == b.to_lowercase().next()
-        {
-            input.remove(0);
-            if let Some(x) = result.pop() {
-                input.insert(0, x);
+    for unit in input {
+        match result.last() {
+            Some(&prev) if rule.reacts(prev, unit) => {
+                result.pop();
             }
-        } else {
-            result.push(a);
+            _ => result.push(unit),
         }
     }
 
     result
 }
 
-fn part2(polymer: &[char]) {
-    let units: Vec<char> = (('a' as u8)..('z' as u8)).map(|b| b as char).collect();
-    let shortest = units
+fn part2(polymer: &[char], reduced: &[char], rule: &Rule) {
+    // With case pairs every unit reacts with exactly one other unit, so removing a unit type
+    // commutes with reacting first and part 2 can start from the already reduced polymer. The
+    // other rules can give a unit several partners, where the order of reactions matters.
+    let start = match rule {
+        Rule::CasePair => reduced,
+        _ => polymer,
+    };
+    let units: BTreeSet<char> = start.iter().map(|&c| rule.unit_type(c)).collect();
+    let units: Vec<char> = units.into_iter().collect();
+    let lengths: Vec<(char, usize)> = units
         .par_iter()
-        .map(|c| {
-            let input: Vec<char> = Vec::from_iter(
-                polymer
-                    .iter()
-                    .filter(|cc| *c != cc.to_lowercase().next().unwrap())
-                    .map(|cc| *cc),
-            );
-            react(input).len()
+        .map(|&c| {
+            let len = react(
+                start.iter().cloned().filter(|&cc| rule.unit_type(cc) != c),
+                rule,
+            )
+            .len();
+            (c, len)
         })
-        .min()
-        .unwrap();
+        .collect();
+
+    for (unit, len) in &lengths {
+        println!("without {}: {}", unit, len);
+    }
+
+    let shortest = lengths.iter().map(|&(_, len)| len).min().unwrap_or(start.len());
 
     println!("part2: {}", shortest);
 }
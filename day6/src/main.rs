@@ -1,17 +1,63 @@
-use std::i32;
+use std::collections::HashMap;
 use std::io::prelude::*;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
 fn main() -> Result<()> {
+    let options = parse_args()?;
     let coordinates = read_input()?;
 
     part1(&coordinates);
-    part2(&coordinates);
+    part2(&coordinates, options.threshold);
+
+    if options.render_ascii || options.render_png.is_some() {
+        let map = AreaMap::build(&coordinates);
+        let region = SafeRegion::build(&coordinates, options.threshold);
+        if options.render_ascii {
+            render_ascii(&coordinates, &map, &region)?;
+        }
+        if let Some(path) = options.render_png {
+            render_png(&coordinates, &map, &region, &path)?;
+            println!("map written to {}", path);
+        }
+    }
 
     Ok(())
 }
 
+struct Options {
+    threshold: i64,
+    render_ascii: bool,
+    render_png: Option<String>,
+}
+
+fn parse_args() -> Result<Options> {
+    let mut options = Options {
+        threshold: 10000,
+        render_ascii: false,
+        render_png: None,
+    };
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--threshold" => {
+                options.threshold = args.next().ok_or("--threshold requires a number")?.parse()?;
+                if options.threshold <= 0 {
+                    return Err("--threshold must be positive".into());
+                }
+            }
+            "--render-ascii" => options.render_ascii = true,
+            "--render-png" => {
+                options.render_png = Some(args.next().ok_or("--render-png requires a file path")?);
+            }
+            _ => return Err(format!("unknown argument: {}", arg).into()),
+        }
+    }
+
+    Ok(options)
+}
+
 fn read_input() -> Result<Vec<Coordinate>> {
     let mut input = String::new();
     std::io::stdin().read_to_string(&mut input)?;
@@ -32,68 +78,373 @@ struct Coordinate {
     y: i32,
 }
 
-impl Coordinate {
-    fn distance(&self, other: &Coordinate) -> i32 {
-        (self.x - other.x).abs() + (self.y - other.y).abs()
-    }
-}
-
 fn part1(coordinates: &[Coordinate]) {
-    let (gw, gh) = grid_size(coordinates);
-    let mut closest_counts = vec![0; coordinates.len()];
-
-    for y in 0..gh {
-        for x in 0..gw {
-            let p = Coordinate { x, y };
-            let distances: Vec<i32> = coordinates.iter().map(|c| c.distance(&p)).collect();
-            if let Some(i) = closest_coordinate(&distances) {
-                if x == 0 || y == 0 || x == (gw - 1) || y == (gh - 1) {
-                    closest_counts[i] = i32::MIN;
-                } else {
-                    closest_counts[i] += 1;
+    let map = AreaMap::build(coordinates);
+    let bounds = map.bounds;
+    let mut areas = vec![Area::Finite(0); coordinates.len()];
+
+    // Every cell outside the bounding box is at the same extra distance from all coordinates as
+    // its nearest cell on the box's edge, so it has the same closest coordinate. An area is
+    // therefore infinite exactly when it reaches the edge of the box.
+    for y in bounds.min_y..=bounds.max_y {
+        for x in bounds.min_x..=bounds.max_x {
+            if let Some(i) = map.get(x, y) {
+                if bounds.on_edge(x, y) {
+                    areas[i] = Area::Infinite;
+                } else if let Area::Finite(n) = areas[i] {
+                    areas[i] = Area::Finite(n + 1);
                 }
-            } else {
-                continue;
             }
         }
     }
 
-    let result = closest_counts.iter().max().unwrap();
+    for (c, area) in coordinates.iter().zip(&areas) {
+        match area {
+            Area::Finite(n) => println!("{},{}: {}", c.x, c.y, n),
+            Area::Infinite => println!("{},{}: infinite", c.x, c.y),
+        }
+    }
+
+    let result = areas
+        .iter()
+        .filter_map(|a| match a {
+            Area::Finite(n) => Some(*n),
+            Area::Infinite => None,
+        })
+        .max()
+        .unwrap_or(0);
 
     println!("part1: {}", result);
 }
 
-fn grid_size(coordinates: &[Coordinate]) -> (i32, i32) {
-    (
-        coordinates.iter().map(|c| c.x).max().unwrap() + 1,
-        coordinates.iter().map(|c| c.y).max().unwrap() + 1,
-    )
+#[derive(Debug, Clone, Copy)]
+enum Area {
+    Finite(usize),
+    Infinite,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    min_x: i32,
+    min_y: i32,
+    max_x: i32,
+    max_y: i32,
 }
 
-fn closest_coordinate(distances: &[i32]) -> Option<usize> {
-    let mut distances: Vec<(usize, i32)> = distances.iter().map(|&d| d).enumerate().collect();
-    distances.sort_by_key(|(_, d)| *d);
-    let (index, distance) = distances.remove(0);
-    if distances.iter().any(|(_, d)| *d == distance) {
-        None
-    } else {
-        Some(index)
+impl Bounds {
+    fn of(coordinates: &[Coordinate]) -> Bounds {
+        Bounds {
+            min_x: coordinates.iter().map(|c| c.x).min().unwrap(),
+            min_y: coordinates.iter().map(|c| c.y).min().unwrap(),
+            max_x: coordinates.iter().map(|c| c.x).max().unwrap(),
+            max_y: coordinates.iter().map(|c| c.y).max().unwrap(),
+        }
+    }
+
+    fn on_edge(&self, x: i32, y: i32) -> bool {
+        x == self.min_x || y == self.min_y || x == self.max_x || y == self.max_y
     }
 }
 
-fn part2(coordinates: &[Coordinate]) {
-    let (gw, gh) = grid_size(coordinates);
-    let mut count = 0;
+/// The index of the unique closest coordinate for every cell in the bounding box, or `None`
+/// where two or more coordinates are equally close.
+struct AreaMap {
+    bounds: Bounds,
+    width: usize,
+    height: usize,
+    labels: Vec<Option<usize>>,
+}
+
+impl AreaMap {
+    // Breadth-first flood fill from all coordinates at once. Within the box the number of steps
+    // to a coordinate is its Manhattan distance, so each cell is reached first by its closest
+    // coordinates, and a cell is tied if the neighbours that reached it a step earlier disagree.
+    fn build(coordinates: &[Coordinate]) -> AreaMap {
+        let bounds = Bounds::of(coordinates);
+        let width = (bounds.max_x - bounds.min_x + 1) as usize;
+        let height = (bounds.max_y - bounds.min_y + 1) as usize;
+        let mut map = AreaMap {
+            bounds,
+            width,
+            height,
+            labels: vec![None; width * height],
+        };
+        let mut distances = vec![usize::MAX; width * height];
+        let mut frontier = Vec::new();
 
-    for y in 0..gh {
-        for x in 0..gw {
-            let p = Coordinate { x, y };
-            let sum_distance: i32 = coordinates.iter().map(|c| p.distance(c)).sum();
-            if sum_distance < 10000 {
-                count += 1;
+        for (i, c) in coordinates.iter().enumerate() {
+            let idx = map.index(c.x, c.y);
+            if distances[idx] == 0 {
+                map.labels[idx] = None;
+            } else {
+                distances[idx] = 0;
+                map.labels[idx] = Some(i);
+                frontier.push(idx);
             }
         }
+
+        let mut distance = 0;
+        while !frontier.is_empty() {
+            let mut next = Vec::new();
+            for &idx in &frontier {
+                let label = map.labels[idx];
+                for n in map.neighbours(idx) {
+                    if distances[n] == usize::MAX {
+                        distances[n] = distance + 1;
+                        map.labels[n] = label;
+                        next.push(n);
+                    } else if distances[n] == distance + 1 && map.labels[n] != label {
+                        map.labels[n] = None;
+                    }
+                }
+            }
+            frontier = next;
+            distance += 1;
+        }
+
+        map
     }
 
-    println!("part2: {}", count)
+    fn index(&self, x: i32, y: i32) -> usize {
+        (y - self.bounds.min_y) as usize * self.width + (x - self.bounds.min_x) as usize
+    }
+
+    fn neighbours(&self, idx: usize) -> impl Iterator<Item = usize> {
+        let (x, y) = (idx % self.width, idx / self.width);
+        let width = self.width;
+        let candidates = [
+            if x > 0 { Some(idx - 1) } else { None },
+            if x + 1 < self.width { Some(idx + 1) } else { None },
+            if y > 0 { Some(idx - width) } else { None },
+            if y + 1 < self.height { Some(idx + width) } else { None },
+        ];
+        IntoIterator::into_iter(candidates).flatten()
+    }
+
+    /// Cells outside the bounding box share the label of the nearest cell on its edge.
+    fn get(&self, x: i32, y: i32) -> Option<usize> {
+        let x = x.clamp(self.bounds.min_x, self.bounds.max_x);
+        let y = y.clamp(self.bounds.min_y, self.bounds.max_y);
+        self.labels[self.index(x, y)]
+    }
+}
+
+fn part2(coordinates: &[Coordinate], threshold: i64) {
+    let region = SafeRegion::build(coordinates, threshold);
+
+    println!("part2: {}", region.count())
+}
+
+/// Cells whose total distance to all coordinates is below the threshold. The total splits into
+/// an x part and a y part, each tabulated once over every row/column that could still be inside.
+struct SafeRegion {
+    min_x: i32,
+    min_y: i32,
+    x_sums: Vec<i64>,
+    y_sums: Vec<i64>,
+    threshold: i64,
+}
+
+impl SafeRegion {
+    fn build(coordinates: &[Coordinate], threshold: i64) -> SafeRegion {
+        let xs: Vec<i32> = coordinates.iter().map(|c| c.x).collect();
+        let ys: Vec<i32> = coordinates.iter().map(|c| c.y).collect();
+        let (min_x, x_sums) = distance_sums(&xs, threshold);
+        let (min_y, y_sums) = distance_sums(&ys, threshold);
+
+        SafeRegion {
+            min_x,
+            min_y,
+            x_sums,
+            y_sums,
+            threshold,
+        }
+    }
+
+    fn contains(&self, x: i32, y: i32) -> bool {
+        let (i, j) = ((x - self.min_x) as usize, (y - self.min_y) as usize);
+        match (self.x_sums.get(i), self.y_sums.get(j)) {
+            (Some(x_sum), Some(y_sum)) if x >= self.min_x && y >= self.min_y => {
+                x_sum + y_sum < self.threshold
+            }
+            _ => false,
+        }
+    }
+
+    /// The smallest box containing the whole region, if it isn't empty.
+    fn extent(&self) -> Option<Bounds> {
+        let min_x_sum = *self.x_sums.iter().min()?;
+        let min_y_sum = *self.y_sums.iter().min()?;
+        let xs: Vec<usize> = (0..self.x_sums.len())
+            .filter(|&i| self.x_sums[i] + min_y_sum < self.threshold)
+            .collect();
+        let ys: Vec<usize> = (0..self.y_sums.len())
+            .filter(|&j| self.y_sums[j] + min_x_sum < self.threshold)
+            .collect();
+
+        Some(Bounds {
+            min_x: self.min_x + *xs.first()? as i32,
+            min_y: self.min_y + *ys.first()? as i32,
+            max_x: self.min_x + *xs.last()? as i32,
+            max_y: self.min_y + *ys.last()? as i32,
+        })
+    }
+
+    // The y sums are convex, so the rows inside the region for any column form a contiguous run
+    // around the minimum, found by walking outwards from it.
+    fn count(&self) -> usize {
+        let centre = (0..self.y_sums.len()).min_by_key(|&j| self.y_sums[j]).unwrap();
+        let mut count = 0;
+
+        for &x_sum in &self.x_sums {
+            let budget = self.threshold - x_sum;
+            if self.y_sums[centre] >= budget {
+                continue;
+            }
+            let below = (0..centre).rev().take_while(|&j| self.y_sums[j] < budget).count();
+            let above = (centre..self.y_sums.len()).take_while(|&j| self.y_sums[j] < budget).count();
+            count += below + above;
+        }
+
+        count
+    }
+}
+
+// Returns the first position and the sum of distances from each position to all the values, over
+// every position where that sum alone could be below the threshold. Outside the values' range
+// the sum grows by the number of values per step, which bounds how far that can be.
+fn distance_sums(values: &[i32], threshold: i64) -> (i32, Vec<i64>) {
+    let n = values.len() as i64;
+    let margin = (threshold / n.max(1) + 1) as i32;
+    let lo = values.iter().cloned().min().unwrap() - margin;
+    let hi = values.iter().cloned().max().unwrap() + margin;
+
+    let mut counts = vec![0i64; (hi - lo + 1) as usize];
+    for &v in values {
+        counts[(v - lo) as usize] += 1;
+    }
+
+    let mut sums = Vec::with_capacity(counts.len());
+    let mut sum: i64 = values.iter().map(|&v| i64::from(v - lo)).sum();
+    let mut at_or_below = 0;
+    for count in counts {
+        sums.push(sum);
+        at_or_below += count;
+        sum += at_or_below - (n - at_or_below);
+    }
+
+    (lo, sums)
+}
+
+// The rendered area covers the bounding box and any part of the safe region that lies outside it.
+fn render_bounds(map: &AreaMap, region: &SafeRegion) -> Bounds {
+    let b = map.bounds;
+    match region.extent() {
+        Some(e) => Bounds {
+            min_x: b.min_x.min(e.min_x),
+            min_y: b.min_y.min(e.min_y),
+            max_x: b.max_x.max(e.max_x),
+            max_y: b.max_y.max(e.max_y),
+        },
+        None => b,
+    }
+}
+
+type Seeds = HashMap<(i32, i32), usize>;
+
+fn seeds(coordinates: &[Coordinate]) -> Seeds {
+    let mut seeds = Seeds::new();
+    for (i, c) in coordinates.iter().enumerate() {
+        seeds.entry((c.x, c.y)).or_insert(i);
+    }
+    seeds
+}
+
+fn render_ascii(coordinates: &[Coordinate], map: &AreaMap, region: &SafeRegion) -> Result<()> {
+    // one letter per area while they last, then pairs of letters so every area stays distinct
+    let width = match coordinates.len() {
+        0..=26 => 1,
+        27..=676 => 2,
+        n => return Err(format!("too many coordinates to render as ASCII: {}", n).into()),
+    };
+    let bounds = render_bounds(map, region);
+    let seeds = seeds(coordinates);
+
+    for y in bounds.min_y..=bounds.max_y {
+        let row: String = (bounds.min_x..=bounds.max_x)
+            .map(|x| match (seeds.get(&(x, y)), map.get(x, y)) {
+                (Some(&i), _) => symbol(i, width).to_ascii_uppercase(),
+                (None, _) if region.contains(x, y) => "#".repeat(width),
+                (None, Some(i)) => symbol(i, width),
+                (None, None) => ".".repeat(width),
+            })
+            .collect();
+        println!("{}", row);
+    }
+
+    Ok(())
+}
+
+fn symbol(i: usize, width: usize) -> String {
+    let letter = |n: usize| (b'a' + n as u8) as char;
+    match width {
+        1 => letter(i).to_string(),
+        _ => format!("{}{}", letter(i / 26), letter(i % 26)),
+    }
+}
+
+fn render_png(
+    coordinates: &[Coordinate],
+    map: &AreaMap,
+    region: &SafeRegion,
+    path: &str,
+) -> Result<()> {
+    let bounds = render_bounds(map, region);
+    let seeds = seeds(coordinates);
+    let width = (bounds.max_x - bounds.min_x + 1) as u32;
+    let height = (bounds.max_y - bounds.min_y + 1) as u32;
+    // scale small maps up so they're still visible
+    let scale = (400 / width.max(height)).max(1);
+
+    let canvas = img::ImageBuffer::from_fn(width * scale, height * scale, |px, py| {
+        let x = bounds.min_x + (px / scale) as i32;
+        let y = bounds.min_y + (py / scale) as i32;
+        if seeds.contains_key(&(x, y)) {
+            return img::Rgb([255, 255, 255]);
+        }
+        let [r, g, b] = match map.get(x, y) {
+            Some(i) => area_colour(i),
+            None => [40, 40, 40],
+        };
+        if region.contains(x, y) {
+            // lighten the safe region towards white
+            img::Rgb([r / 2 + 128, g / 2 + 128, b / 2 + 128])
+        } else {
+            img::Rgb([r, g, b])
+        }
+    });
+
+    canvas.save(path)?;
+
+    Ok(())
+}
+
+// Spread hues around the colour wheel by the golden angle so neighbouring indices contrast.
+fn area_colour(i: usize) -> [u8; 3] {
+    let hue = (i as f64 * 137.508) % 360.0;
+    let (s, v) = (0.75, 0.8);
+    let c = v * s;
+    let h = hue / 60.0;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = v - c;
+    let to_byte = |f: f64| ((f + m) * 255.0).round() as u8;
+    [to_byte(r), to_byte(g), to_byte(b)]
 }
@@ -1,167 +1,398 @@
+mod schedule;
+
+use crate::schedule::{Assignment, Schedule};
 use lazy_static::lazy_static;
 use regex::Regex;
-use std::collections::{HashMap, HashSet};
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap, HashMap};
 use std::io::prelude::*;
-use std::iter::FromIterator;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
+type Step = String;
+type Durations = HashMap<Step, i64>;
+
 fn main() -> Result<()> {
-    let pairs = read_input()?;
+    let options = parse_args()?;
+    let (pairs, mut durations) = read_input()?;
+    if let Some(path) = &options.durations_path {
+        durations.extend(parse_durations(&std::fs::read_to_string(path)?)?);
+    }
+
+    let graph = Graph::build(&pairs);
+    check_rules(&graph)?;
+
+    part1(&graph);
 
-    part1(&pairs);
-    part2(&pairs, 60, 5);
+    // only the timed parts need durations, so the step order is printed even if some are missing
+    let step_times = step_times(&graph, &durations, 60)?;
+    let schedule = part2(&graph, &step_times, 5);
+
+    if options.gantt {
+        schedule::print_gantt(&schedule);
+    }
+    if options.csv {
+        schedule::print_csv(&schedule);
+    }
+    if options.json {
+        schedule::print_json(&schedule)?;
+    }
+    if options.stats {
+        schedule::print_stats(&schedule);
+    }
+    if options.critical_path {
+        print_critical_path(&graph, &step_times);
+    }
+    if options.dot {
+        print_dot(&graph, &step_times);
+    }
 
     Ok(())
 }
 
-fn read_input() -> Result<Vec<(char, char)>> {
+#[derive(Default)]
+struct Options {
+    durations_path: Option<String>,
+    gantt: bool,
+    csv: bool,
+    json: bool,
+    stats: bool,
+    critical_path: bool,
+    dot: bool,
+}
+
+fn parse_args() -> Result<Options> {
+    let mut options = Options::default();
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--durations" => {
+                options.durations_path = Some(args.next().ok_or("--durations requires a file path")?);
+            }
+            "--gantt" => options.gantt = true,
+            "--csv" => options.csv = true,
+            "--json" => options.json = true,
+            "--stats" => options.stats = true,
+            "--critical-path" => options.critical_path = true,
+            "--dot" => options.dot = true,
+            _ => return Err(format!("unknown argument: {}", arg).into()),
+        }
+    }
+
+    Ok(options)
+}
+
+// The instructions may be followed by a blank line and a table of step durations.
+fn read_input() -> Result<(Vec<(Step, Step)>, Durations)> {
     let mut input = String::new();
     std::io::stdin().read_to_string(&mut input)?;
-    Ok(input.trim_end().lines().map(parse_instruction).collect())
+    let mut sections = input.trim_end().splitn(2, "\n\n");
+    let pairs = sections
+        .next()
+        .unwrap_or("")
+        .lines()
+        .map(parse_instruction)
+        .collect::<Result<_>>()?;
+    let durations = parse_durations(sections.next().unwrap_or(""))?;
+    Ok((pairs, durations))
 }
 
-fn parse_instruction(s: &str) -> (char, char) {
+fn parse_instruction(s: &str) -> Result<(Step, Step)> {
     lazy_static! {
         static ref re: Regex =
-            Regex::new(r"Step ([A-Z]) must be finished before step ([A-Z]) can begin.").unwrap();
+            Regex::new(r"Step (\S+) must be finished before step (\S+) can begin.").unwrap();
     }
 
-    let caps = re.captures(s).unwrap();
+    let caps = re.captures(s).ok_or_else(|| format!("invalid instruction: {}", s))?;
+
+    Ok((caps[1].to_string(), caps[2].to_string()))
+}
+
+// One `<step> <seconds>` entry per line.
+fn parse_durations(s: &str) -> Result<Durations> {
+    let mut durations = Durations::new();
+
+    for line in s.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let mut fields = line.split_whitespace();
+        match (fields.next(), fields.next(), fields.next()) {
+            (Some(step), Some(seconds), None) => {
+                let seconds: i64 = seconds.parse().map_err(|_| format!("invalid duration: {}", line))?;
+                if seconds < 0 {
+                    return Err(format!("negative duration: {}", line).into());
+                }
+                durations.insert(step.to_string(), seconds);
+            }
+            _ => return Err(format!("invalid duration: {}", line).into()),
+        }
+    }
 
-    (
-        caps[1].chars().next().unwrap(),
-        caps[2].chars().next().unwrap(),
-    )
+    Ok(durations)
 }
 
-fn part1(pairs: &[(char, char)]) {
-    let steps = collect_steps(pairs);
-    let rules = build_rules(pairs);
+fn part1(graph: &Graph) {
+    let mut in_degrees = graph.in_degrees();
+    let mut available: BinaryHeap<Reverse<usize>> = graph.roots(&in_degrees);
     let mut completed = Vec::new();
 
-    while let Some(next) = find_next(&steps, &completed, &rules) {
-        completed.push(next);
+    while let Some(Reverse(next)) = available.pop() {
+        completed.push(graph.steps[next].clone());
+        graph.complete(next, &mut in_degrees, &mut available);
     }
 
-    let result = String::from_iter(completed.into_iter());
-
-    println!("part1: {}", result);
+    println!("part1: {}", join_steps(&completed));
 }
 
-fn collect_steps(pairs: &[(char, char)]) -> Vec<char> {
-    let mut steps: HashSet<char> = HashSet::new();
-
-    for &(from, to) in pairs {
-        steps.insert(from);
-        steps.insert(to);
+// Single letter steps are run together as in the puzzle answer, longer names are separated.
+fn join_steps(steps: &[Step]) -> String {
+    if steps.iter().all(|s| s.chars().count() == 1) {
+        steps.concat()
+    } else {
+        steps.join(",")
     }
+}
 
-    steps.into_iter().collect()
+/// The steps in alphabetical order, so that comparing indices compares names, with the
+/// instructions as edges between them in both directions.
+struct Graph {
+    steps: Vec<Step>,
+    prerequisites: Vec<Vec<usize>>,
+    dependents: Vec<Vec<usize>>,
 }
 
-fn build_rules(pairs: &[(char, char)]) -> HashMap<char, Vec<char>> {
-    let mut rules: HashMap<char, Vec<char>> = HashMap::new();
+impl Graph {
+    fn build(pairs: &[(Step, Step)]) -> Graph {
+        let names: BTreeSet<&Step> = pairs.iter().flat_map(|(from, to)| vec![from, to]).collect();
+        let steps: Vec<Step> = names.into_iter().cloned().collect();
+        let index = |step: &Step| steps.binary_search(step).unwrap();
+
+        let mut prerequisites = vec![Vec::new(); steps.len()];
+        let mut dependents = vec![Vec::new(); steps.len()];
+        for (from, to) in pairs {
+            prerequisites[index(to)].push(index(from));
+            dependents[index(from)].push(index(to));
+        }
 
-    for &(from, to) in pairs {
-        rules.entry(to).or_insert_with(|| Vec::new()).push(from);
+        Graph {
+            steps,
+            prerequisites,
+            dependents,
+        }
     }
 
-    rules
-}
+    fn in_degrees(&self) -> Vec<usize> {
+        self.prerequisites.iter().map(|p| p.len()).collect()
+    }
 
-fn find_next(steps: &[char], completed: &[char], rules: &HashMap<char, Vec<char>>) -> Option<char> {
-    let mut available = Vec::new();
+    fn roots(&self, in_degrees: &[usize]) -> BinaryHeap<Reverse<usize>> {
+        (0..self.steps.len())
+            .filter(|&i| in_degrees[i] == 0)
+            .map(Reverse)
+            .collect()
+    }
 
-    for step in steps {
-        if !completed.contains(step) {
-            if let Some(rule) = rules.get(step) {
-                if rule.iter().all(|prereq| completed.contains(prereq)) {
-                    available.push(*step);
-                }
-            } else {
-                available.push(*step);
+    // Marks a step as done, making any dependents that were only waiting on it available.
+    fn complete(&self, step: usize, in_degrees: &mut [usize], available: &mut BinaryHeap<Reverse<usize>>) {
+        for &dependent in &self.dependents[step] {
+            in_degrees[dependent] -= 1;
+            if in_degrees[dependent] == 0 {
+                available.push(Reverse(dependent));
             }
         }
     }
 
-    available.sort();
-    available.into_iter().next()
-}
+    // Steps in an order where every step comes after all of its prerequisites.
+    fn topological_order(&self) -> Vec<usize> {
+        let mut in_degrees = self.in_degrees();
+        let mut available = self.roots(&in_degrees);
+        let mut order = Vec::with_capacity(self.steps.len());
+        while let Some(Reverse(next)) = available.pop() {
+            order.push(next);
+            self.complete(next, &mut in_degrees, &mut available);
+        }
+        order
+    }
 
-fn part2(pairs: &[(char, char)], base_time_per_step: i32, num_workers: usize) {
-    let mut steps = collect_steps(pairs);
-    steps.sort();
+    /// The chain of steps with the longest total duration, which bounds the total time no matter
+    /// how many workers there are, along with that duration.
+    fn critical_path(&self, step_times: &[i64]) -> (Vec<usize>, i64) {
+        let mut finish = vec![0; self.steps.len()];
+        let mut previous: Vec<Option<usize>> = vec![None; self.steps.len()];
 
-    let rules = build_rules(pairs);
-    let mut completed = Vec::new();
-    let mut workers = vec![Worker(None); num_workers];
-    let mut time = 0;
+        for step in self.topological_order() {
+            let before = self.prerequisites[step].iter().cloned().max_by_key(|&p| finish[p]);
+            finish[step] = before.map_or(0, |p| finish[p]) + step_times[step];
+            previous[step] = before;
+        }
 
-    loop {
-        for worker in workers.iter_mut() {
-            if worker.is_idle() {
-                if let Some(next) = find_next(&steps, &completed, &rules) {
-                    steps.remove(steps.binary_search(&next).unwrap());
-                    worker.assign(next, time_for_step(base_time_per_step, next));
-                }
-            }
+        let mut path = Vec::new();
+        let mut step = (0..self.steps.len()).max_by_key(|&i| finish[i]);
+        let length = step.map_or(0, |i| finish[i]);
+        while let Some(i) = step {
+            path.push(i);
+            step = previous[i];
         }
+        path.reverse();
 
-        print_status(time, &workers, &completed);
+        (path, length)
+    }
 
-        for worker in workers.iter_mut() {
-            if let Some(step) = worker.work() {
-                completed.push(step);
+    fn depends_on_itself(&self, step: usize) -> bool {
+        let mut seen = vec![false; self.steps.len()];
+        let mut stack = self.prerequisites[step].clone();
+        while let Some(prereq) = stack.pop() {
+            if prereq == step {
+                return true;
+            }
+            if !seen[prereq] {
+                seen[prereq] = true;
+                stack.extend(&self.prerequisites[prereq]);
             }
         }
+        false
+    }
+}
 
-        time += 1;
+fn check_rules(graph: &Graph) -> Result<()> {
+    // Complete every step that can be completed. Whatever is left over either lies on a cycle or
+    // waits on one.
+    let mut completed = vec![false; graph.steps.len()];
+    for step in graph.topological_order() {
+        completed[step] = true;
+    }
 
-        if steps.is_empty() && workers.iter().all(|w| w.is_idle()) {
-            print_status(time, &workers, &completed);
-            break;
-        }
+    let remaining: Vec<usize> = (0..graph.steps.len()).filter(|&i| !completed[i]).collect();
+    if remaining.is_empty() {
+        return Ok(());
     }
 
-    println!("part2: {}", time);
+    let (cyclic, blocked): (Vec<usize>, Vec<usize>) =
+        remaining.into_iter().partition(|&i| graph.depends_on_itself(i));
+
+    let names = |steps: Vec<usize>| {
+        steps.iter().map(|&i| graph.steps[i].as_str()).collect::<Vec<_>>().join(", ")
+    };
+    let mut message = format!("dependency cycle between steps {}", names(cyclic));
+    if !blocked.is_empty() {
+        message += &format!("; steps {} can never start", names(blocked));
+    }
+    Err(message.into())
 }
 
-fn time_for_step(base: i32, step: char) -> i32 {
-    base + (step as i32) - ('A' as i32) + 1
+fn step_times(graph: &Graph, durations: &Durations, base_time_per_step: i64) -> Result<Vec<i64>> {
+    graph
+        .steps
+        .iter()
+        .map(|s| time_for_step(base_time_per_step, s, durations))
+        .collect()
 }
 
-fn print_status(time: i32, workers: &[Worker], completed: &[char]) {
-    print!("{}\t", time);
-    for worker in workers {
-        print!("{:?}\t", worker.0.unwrap_or(('.', 0)));
-    }
-    println!("{}", String::from_iter(completed.iter()));
+fn part2(graph: &Graph, step_times: &[i64], num_workers: usize) -> Schedule {
+    let schedule = simulate(graph, step_times, num_workers);
+
+    println!("part2: {}", schedule.total_time);
+
+    schedule
 }
 
-#[derive(Clone)]
-struct Worker(Option<(char, i32)>);
+// Rather than ticking every second, jump straight from one step completion to the next. Idle
+// workers, lowest numbered first, take available steps in alphabetical order whenever something
+// completes.
+fn simulate(graph: &Graph, step_times: &[i64], num_workers: usize) -> Schedule {
+    let mut in_degrees = graph.in_degrees();
+    let mut available = graph.roots(&in_degrees);
+    let mut idle: BinaryHeap<Reverse<usize>> = (0..num_workers).map(Reverse).collect();
+    let mut running: BinaryHeap<Reverse<(i64, usize, usize)>> = BinaryHeap::new();
+    let mut assignments = Vec::new();
+    let mut time = 0;
+
+    loop {
+        while !idle.is_empty() && !available.is_empty() {
+            let Reverse(worker) = idle.pop().unwrap();
+            let Reverse(step) = available.pop().unwrap();
+            let end = time + step_times[step];
+            assignments.push(Assignment {
+                worker,
+                step: graph.steps[step].clone(),
+                start: time,
+                end,
+            });
+            running.push(Reverse((end, worker, step)));
+        }
+
+        time = match running.peek() {
+            Some(&Reverse((end, _, _))) => end,
+            None => break,
+        };
+        while let Some(&Reverse((end, worker, step))) = running.peek() {
+            if end != time {
+                break;
+            }
+            running.pop();
+            idle.push(Reverse(worker));
+            graph.complete(step, &mut in_degrees, &mut available);
+        }
+    }
 
-impl Worker {
-    fn is_idle(&self) -> bool {
-        self.0.is_none()
+    Schedule {
+        num_workers,
+        total_time: time,
+        assignments,
     }
+}
 
-    fn assign(&mut self, step: char, remaining: i32) {
-        assert!(self.is_idle());
-        self.0 = Some((step, remaining));
+// Steps missing from the durations table fall back to the puzzle's rule for single letters.
+fn time_for_step(base: i64, step: &str, durations: &Durations) -> Result<i64> {
+    if let Some(&duration) = durations.get(step) {
+        return Ok(duration);
     }
 
-    fn work(&mut self) -> Option<char> {
-        if let Some((step, remaining)) = self.0 {
-            if remaining > 1 {
-                self.0.replace((step, remaining - 1));
-                None
-            } else {
-                self.0.take().map(|(step, _)| step)
-            }
-        } else {
-            None
+    let mut chars = step.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_uppercase() => Ok(base + (c as i64) - ('A' as i64) + 1),
+        _ => Err(format!("no duration given for step {}", step).into()),
+    }
+}
+
+// Finding the true minimum is a hard scheduling problem, so this is the fewest workers with which
+// the scheduler above finishes within the critical path. Fewer than the total work divided by the
+// critical path can never be enough.
+fn min_workers_for(graph: &Graph, step_times: &[i64], target: i64) -> usize {
+    let total_work: i64 = step_times.iter().sum();
+    let lower_bound = if target > 0 {
+        ((total_work + target - 1) / target).max(1) as usize
+    } else {
+        1
+    };
+
+    (lower_bound..graph.steps.len().max(1))
+        .find(|&n| simulate(graph, step_times, n).total_time <= target)
+        .unwrap_or_else(|| graph.steps.len().max(1))
+}
+
+fn print_critical_path(graph: &Graph, step_times: &[i64]) {
+    let (path, length) = graph.critical_path(step_times);
+    let names: Vec<&str> = path.iter().map(|&i| graph.steps[i].as_str()).collect();
+
+    println!("critical path: {} ({} s)", names.join(" -> "), length);
+    println!("workers needed: {}", min_workers_for(graph, step_times, length));
+}
+
+fn print_dot(graph: &Graph, step_times: &[i64]) {
+    let (path, _) = graph.critical_path(step_times);
+    let on_path = |i: usize| path.contains(&i);
+    let critical_edge = |from: usize, to: usize| path.windows(2).any(|w| w == [from, to]);
+
+    println!("digraph steps {{");
+    for (i, step) in graph.steps.iter().enumerate() {
+        let colour = if on_path(i) { ", color=red" } else { "" };
+        println!("    {:?} [label=\"{}\\n{} s\"{}];", step, step.escape_default(), step_times[i], colour);
+    }
+    for (from, dependents) in graph.dependents.iter().enumerate() {
+        for &to in dependents {
+            let colour = if critical_edge(from, to) { " [color=red]" } else { "" };
+            println!("    {:?} -> {:?}{};", graph.steps[from], graph.steps[to], colour);
         }
     }
+    println!("}}");
 }
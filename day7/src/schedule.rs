@@ -0,0 +1,162 @@
+use serde_derive::Serialize;
+use std::collections::HashMap;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Debug, Serialize)]
+pub struct Schedule {
+    pub num_workers: usize,
+    pub total_time: i64,
+    pub assignments: Vec<Assignment>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Assignment {
+    pub worker: usize,
+    pub step: String,
+    pub start: i64,
+    pub end: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkerStats {
+    pub worker: usize,
+    pub busy: i64,
+    pub idle: i64,
+    pub utilisation: f64,
+}
+
+impl Schedule {
+    pub fn worker_stats(&self) -> Vec<WorkerStats> {
+        (0..self.num_workers)
+            .map(|worker| {
+                let busy = self
+                    .assignments
+                    .iter()
+                    .filter(|a| a.worker == worker)
+                    .map(|a| a.end - a.start)
+                    .sum();
+                let utilisation = if self.total_time > 0 {
+                    busy as f64 / self.total_time as f64
+                } else {
+                    0.0
+                };
+                WorkerStats {
+                    worker,
+                    busy,
+                    idle: self.total_time - busy,
+                    utilisation,
+                }
+            })
+            .collect()
+    }
+}
+
+const GANTT_WIDTH: i64 = 100;
+
+// Long schedules are squeezed into at most GANTT_WIDTH columns, each showing the step that was
+// running at the start of its time slice.
+pub fn print_gantt(schedule: &Schedule) {
+    let scale = ((schedule.total_time + GANTT_WIDTH - 1) / GANTT_WIDTH).max(1);
+    let columns = (schedule.total_time + scale - 1) / scale;
+    let symbols = gantt_symbols(schedule);
+
+    println!("one column = {} s", scale);
+    for worker in 0..schedule.num_workers {
+        let row: String = (0..columns)
+            .map(|col| {
+                let t = col * scale;
+                schedule
+                    .assignments
+                    .iter()
+                    .find(|a| a.worker == worker && a.start <= t && t < a.end)
+                    .map_or('.', |a| symbols[&a.step])
+            })
+            .collect();
+        println!("worker {:<3} |{}|", worker, row);
+    }
+
+    if symbols
+        .iter()
+        .any(|(step, &symbol)| step.chars().ne(std::iter::once(symbol)))
+    {
+        let mut legend: Vec<(&String, &char)> = symbols.iter().collect();
+        legend.sort_by_key(|&(_, &symbol)| symbol);
+        for (step, symbol) in legend {
+            println!("{} = {}", symbol, step);
+        }
+    }
+}
+
+// Single letter steps stand for themselves, as in the puzzle. Otherwise every step gets its own
+// symbol in order of first appearance, explained by a legend under the chart.
+fn gantt_symbols(schedule: &Schedule) -> HashMap<String, char> {
+    let mut steps: Vec<&String> = Vec::new();
+    for a in &schedule.assignments {
+        if !steps.contains(&&a.step) {
+            steps.push(&a.step);
+        }
+    }
+
+    let single_chars = steps
+        .iter()
+        .all(|step| step.chars().count() == 1 && !step.starts_with(['.', '|']));
+    if single_chars {
+        return steps
+            .into_iter()
+            .map(|step| (step.clone(), step.chars().next().unwrap()))
+            .collect();
+    }
+
+    let pool = ('A'..='Z')
+        .chain('a'..='z')
+        .chain('0'..='9')
+        .chain('\u{100}'..);
+    steps.into_iter().cloned().zip(pool).collect()
+}
+
+pub fn print_csv(schedule: &Schedule) {
+    println!("worker,step,start,end");
+    for a in &schedule.assignments {
+        println!("{},{},{},{}", a.worker, csv_field(&a.step), a.start, a.end);
+    }
+}
+
+// Step names can contain any non-whitespace character, including commas and quotes.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+pub fn print_json(schedule: &Schedule) -> Result<()> {
+    #[derive(Serialize)]
+    struct Export<'a> {
+        #[serde(flatten)]
+        schedule: &'a Schedule,
+        workers: Vec<WorkerStats>,
+    }
+
+    let export = Export {
+        schedule,
+        workers: schedule.worker_stats(),
+    };
+    println!("{}", serde_json::to_string_pretty(&export)?);
+
+    Ok(())
+}
+
+pub fn print_stats(schedule: &Schedule) {
+    println!("worker\tbusy\tidle\tutilisation");
+    for stats in schedule.worker_stats() {
+        println!(
+            "{}\t{}\t{}\t{:.1}%",
+            stats.worker,
+            stats.busy,
+            stats.idle,
+            stats.utilisation * 100.0
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use crate::Node;
+
+// Trees are written as nested `{"children":[...],"metadata":[...]}` objects. Both directions use
+// an explicit stack, like the flat format, so deep trees can't overflow the call stack.
+
+pub fn to_json(tree: &Node) -> String {
+    let mut out = String::from("{\"children\":[");
+    let mut stack = vec![(tree, 0)];
+
+    while let Some((node, next_child)) = stack.pop() {
+        if let Some(child) = node.children.get(next_child) {
+            if next_child > 0 {
+                out.push(',');
+            }
+            stack.push((node, next_child + 1));
+            out.push_str("{\"children\":[");
+            stack.push((child, 0));
+        } else {
+            let metadata: Vec<String> = node.metadata.iter().map(|m| m.to_string()).collect();
+            out.push_str("],\"metadata\":[");
+            out.push_str(&metadata.join(","));
+            out.push_str("]}");
+        }
+    }
+
+    out
+}
+
+#[derive(Debug)]
+pub struct JsonError {
+    offset: usize,
+    message: String,
+}
+
+impl std::fmt::Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "invalid JSON at byte {}: {}", self.offset, self.message)
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+// An object that has been opened but not yet closed.
+#[derive(Default)]
+struct Partial {
+    children: Option<Vec<Node>>,
+    metadata: Option<Vec<usize>>,
+}
+
+enum Expect {
+    Object,
+    FieldOrEnd { first: bool },
+}
+
+pub fn from_json(s: &str) -> std::result::Result<Node, JsonError> {
+    let mut reader = Reader {
+        input: s.as_bytes(),
+        pos: 0,
+    };
+    let mut stack: Vec<Partial> = Vec::new();
+    let mut expect = Expect::Object;
+
+    loop {
+        match expect {
+            Expect::Object => {
+                reader.expect(b'{')?;
+                stack.push(Partial::default());
+                expect = Expect::FieldOrEnd { first: true };
+            }
+            Expect::FieldOrEnd { first } => {
+                if reader.peek() == Some(b'}') {
+                    reader.pos += 1;
+                    let partial = stack.pop().unwrap();
+                    let node = match (partial.children, partial.metadata) {
+                        (Some(children), Some(metadata)) => Node::new(children, metadata),
+                        (None, _) => return Err(reader.error("missing field `children`")),
+                        (_, None) => return Err(reader.error("missing field `metadata`")),
+                    };
+
+                    match stack.last_mut() {
+                        None => {
+                            reader.skip_whitespace();
+                            if reader.pos < reader.input.len() {
+                                return Err(reader.error("trailing characters"));
+                            }
+                            return Ok(node);
+                        }
+                        Some(parent) => {
+                            parent.children.as_mut().unwrap().push(node);
+                            expect = if reader.array_continues()? {
+                                Expect::Object
+                            } else {
+                                Expect::FieldOrEnd { first: false }
+                            };
+                        }
+                    }
+                    continue;
+                }
+
+                if !first {
+                    reader.expect(b',')?;
+                }
+                let key = reader.string()?;
+                reader.expect(b':')?;
+                let partial = stack.last_mut().unwrap();
+
+                match key.as_str() {
+                    "metadata" if partial.metadata.is_none() => {
+                        partial.metadata = Some(reader.numbers()?);
+                        expect = Expect::FieldOrEnd { first: false };
+                    }
+                    "children" if partial.children.is_none() => {
+                        partial.children = Some(Vec::new());
+                        reader.expect(b'[')?;
+                        expect = if reader.peek() == Some(b']') {
+                            reader.pos += 1;
+                            Expect::FieldOrEnd { first: false }
+                        } else {
+                            Expect::Object
+                        };
+                    }
+                    "metadata" | "children" => {
+                        return Err(reader.error(&format!("duplicate field `{}`", key)))
+                    }
+                    _ => return Err(reader.error(&format!("unknown field `{}`", key))),
+                }
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self, message: &str) -> JsonError {
+        JsonError {
+            offset: self.pos,
+            message: message.to_string(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.input.len() && self.input[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.input.get(self.pos).cloned()
+    }
+
+    fn expect(&mut self, c: u8) -> std::result::Result<(), JsonError> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", c as char)))
+        }
+    }
+
+    // Field names only, so escapes aren't supported.
+    fn string(&mut self) -> std::result::Result<String, JsonError> {
+        self.expect(b'"')?;
+        let start = self.pos;
+        while self.pos < self.input.len() && self.input[self.pos] != b'"' {
+            if self.input[self.pos] == b'\\' {
+                return Err(self.error("escapes are not supported in field names"));
+            }
+            self.pos += 1;
+        }
+        let s = String::from_utf8_lossy(&self.input[start..self.pos]).into_owned();
+        self.expect(b'"')?;
+        Ok(s)
+    }
+
+    fn number(&mut self) -> std::result::Result<usize, JsonError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self.pos < self.input.len() && self.input[self.pos].is_ascii_digit() {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.input[start..self.pos])
+            .unwrap()
+            .parse()
+            .map_err(|_| JsonError {
+                offset: start,
+                message: "expected a non-negative integer".to_string(),
+            })
+    }
+
+    fn numbers(&mut self) -> std::result::Result<Vec<usize>, JsonError> {
+        let mut numbers = Vec::new();
+        self.expect(b'[')?;
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(numbers);
+        }
+        loop {
+            numbers.push(self.number()?);
+            if !self.array_continues()? {
+                return Ok(numbers);
+            }
+        }
+    }
+
+    // Consumes the `,` or `]` after an array element, returning whether another element follows.
+    fn array_continues(&mut self) -> std::result::Result<bool, JsonError> {
+        match self.peek() {
+            Some(b',') => {
+                self.pos += 1;
+                Ok(true)
+            }
+            Some(b']') => {
+                self.pos += 1;
+                Ok(false)
+            }
+            _ => Err(self.error("expected `,` or `]`")),
+        }
+    }
+}
@@ -1,56 +1,257 @@
+mod json;
+
+use std::collections::HashMap;
 use std::io::prelude::*;
-use std::iter::FromIterator;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
 fn main() -> Result<()> {
-    let data = read_input()?;
+    let options = parse_args()?;
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
+
+    let tree = if options.from_json {
+        json::from_json(&input).map_err(|e| e.to_string())?
+    } else {
+        build_tree(&parse_numbers(&input)?).map_err(|e| e.to_string())?
+    };
 
-    part1(&data);
-    part2(&data);
+    match options.output {
+        Output::Answers => {
+            part1(&tree);
+            part2(&tree, options.strict)?;
+        }
+        Output::Flat => println!("{}", format_numbers(&tree.encode())),
+        Output::Json => println!("{}", json::to_json(&tree)),
+        Output::RoundTrip => round_trip(&tree)?,
+        Output::Inspect(path) => {
+            let node = tree
+                .at_path(&path)
+                .ok_or_else(|| format!("no node at path {}", format_path(&path)))?;
+            node.print(&path)?;
+            print_stats(node, &path);
+        }
+    }
 
     Ok(())
 }
 
-fn read_input() -> Result<Vec<usize>> {
-    let mut input = String::new();
-    std::io::stdin().read_to_string(&mut input)?;
-    Ok(input
-        .trim_end()
-        .split_whitespace()
-        .map(|s| s.parse::<usize>().unwrap())
-        .collect())
+struct Options {
+    from_json: bool,
+    strict: bool,
+    output: Output,
+}
+
+enum Output {
+    Answers,
+    Flat,
+    Json,
+    RoundTrip,
+    Inspect(Vec<usize>),
 }
 
-fn part1(data: &[usize]) {
-    let tree = build_tree(&mut data.iter().map(|d| *d));
+fn parse_args() -> Result<Options> {
+    let mut options = Options {
+        from_json: false,
+        strict: false,
+        output: Output::Answers,
+    };
+
+    let mut args = std::env::args().skip(1);
 
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from-json" => options.from_json = true,
+            "--strict" => options.strict = true,
+            "--to-flat" => options.output = Output::Flat,
+            "--to-json" => options.output = Output::Json,
+            "--round-trip" => options.output = Output::RoundTrip,
+            "--inspect" => options.output = Output::Inspect(Vec::new()),
+            "--path" => {
+                let path = args.next().ok_or("--path requires a path like 0/2/1")?;
+                options.output = Output::Inspect(parse_path(&path)?);
+            }
+            _ => return Err(format!("unknown argument: {}", arg).into()),
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_numbers(input: &str) -> Result<Vec<usize>> {
+    let mut data = Vec::new();
+    for s in input.split_whitespace() {
+        data.push(s.parse::<usize>()?);
+    }
+    Ok(data)
+}
+
+fn format_numbers(data: &[usize]) -> String {
+    data.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+// Checks that the tree survives being written out and read back in both formats.
+fn round_trip(tree: &Node) -> Result<()> {
+    let flat = tree.encode();
+    let from_flat = build_tree(&flat).map_err(|e| e.to_string())?;
+    if from_flat != *tree {
+        return Err("tree changed after a round trip through the flat format".into());
+    }
+
+    let json = json::to_json(tree);
+    let from_json = json::from_json(&json).map_err(|e| e.to_string())?;
+    if from_json != *tree {
+        return Err("tree changed after a round trip through JSON".into());
+    }
+
+    println!("round trip ok: {} numbers, {} bytes of JSON", flat.len(), json.len());
+
+    Ok(())
+}
+
+fn part1(tree: &Node) {
     let result = tree.sum_metadata();
 
     println!("part1: {}", result);
 }
 
-fn build_tree(data: &mut Iterator<Item = usize>) -> Node {
-    let num_children = data.next().unwrap();
-    let num_metadata = data.next().unwrap();
-    let mut children = Vec::new();
-    for _ in 0..num_children {
-        let child = build_tree(data);
-        children.push(child);
+#[derive(Debug)]
+enum ParseError {
+    Truncated { offset: usize, path: Vec<usize>, reading: &'static str },
+    TrailingData { offset: usize, count: usize },
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseError::Truncated { offset, path, reading } => write!(
+                f,
+                "input ends at offset {} while reading the {} of node {}",
+                offset,
+                reading,
+                format_path(path)
+            ),
+            ParseError::TrailingData { offset, count } => {
+                write!(f, "{} unused numbers after the tree at offset {}", count, offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn parse_path(s: &str) -> Result<Vec<usize>> {
+    let mut path = Vec::new();
+    for index in s.split('/').filter(|i| !i.is_empty() && *i != "root") {
+        path.push(index.parse()?);
     }
-    let mut metadata = Vec::new();
-    for _ in 0..num_metadata {
-        metadata.push(data.next().unwrap());
+    Ok(path)
+}
+
+/// A node's position as the child indices leading to it from the root, e.g. `0/2/1`.
+fn format_path(path: &[usize]) -> String {
+    if path.is_empty() {
+        "root".to_string()
+    } else {
+        path.iter().map(|i| i.to_string()).collect::<Vec<_>>().join("/")
     }
-    Node { children, metadata }
 }
 
-fn part2(data: &[usize]) {
-    let tree = build_tree(&mut data.iter().map(|d| *d));
-    let result = tree.value();
+// Nodes under construction, from the root down to the one currently being read. Using an
+// explicit stack rather than recursion means deep trees can't overflow the call stack.
+struct Partial {
+    num_children: usize,
+    num_metadata: usize,
+    children: Vec<Node>,
+}
+
+fn build_tree(data: &[usize]) -> std::result::Result<Node, ParseError> {
+    let mut pos = 0;
+    let mut stack: Vec<Partial> = Vec::new();
+
+    // the path of the node on top of the stack, or of its next child when it's being started
+    let path = |stack: &[Partial], next_child: bool| -> Vec<usize> {
+        let n = if next_child { stack.len() } else { stack.len() - 1 };
+        stack[..n].iter().map(|p| p.children.len()).collect()
+    };
+
+    loop {
+        let starting_child = match stack.last() {
+            Some(p) => p.children.len() < p.num_children,
+            None => true,
+        };
+
+        if starting_child {
+            if 2 > data.len() - pos {
+                return Err(ParseError::Truncated {
+                    offset: data.len(),
+                    path: path(&stack, true),
+                    reading: "header",
+                });
+            }
+            stack.push(Partial {
+                num_children: data[pos],
+                num_metadata: data[pos + 1],
+                children: Vec::new(),
+            });
+            pos += 2;
+        } else {
+            let num_metadata = stack.last().unwrap().num_metadata;
+            if num_metadata > data.len() - pos {
+                return Err(ParseError::Truncated {
+                    offset: data.len(),
+                    path: path(&stack, false),
+                    reading: "metadata",
+                });
+            }
+            let partial = stack.pop().unwrap();
+            let node = Node::new(partial.children, data[pos..(pos + num_metadata)].to_vec());
+            pos += num_metadata;
+
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(node),
+                None if pos < data.len() => {
+                    return Err(ParseError::TrailingData {
+                        offset: pos,
+                        count: data.len() - pos,
+                    })
+                }
+                None => return Ok(node),
+            }
+        }
+    }
+}
+
+fn part2(tree: &Node, strict: bool) -> Result<()> {
+    let result = tree.compute_value(strict).map_err(|e| e.to_string())?;
     println!("part2: {}", result);
+    Ok(())
+}
+
+/// A metadata entry that doesn't refer to any of its node's children.
+#[derive(Debug)]
+struct ValueError {
+    path: Vec<usize>,
+    metadata_index: usize,
+    reference: usize,
+    num_children: usize,
+}
+
+impl std::fmt::Display for ValueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "metadata entry {} of node {} refers to child {} but the node has {} children",
+            self.metadata_index,
+            format_path(&self.path),
+            self.reference,
+            self.num_children
+        )
+    }
 }
 
+impl std::error::Error for ValueError {}
+
 #[derive(Debug)]
 struct Node {
     children: Vec<Node>,
@@ -58,34 +259,210 @@ struct Node {
 }
 
 impl Node {
-    fn print(&self, indent: usize) {
-        println!(
-            "{}Node: {:?}",
-            String::from_iter(vec![' '; indent].into_iter()),
-            self.metadata
-        );
-        for child in &self.children {
-            child.print(indent + 2);
+    fn new(children: Vec<Node>, metadata: Vec<usize>) -> Node {
+        Node { children, metadata }
+    }
+
+    // The inverse of build_tree: each node's header, then its children, then its metadata.
+    fn encode(&self) -> Vec<usize> {
+        let mut data = vec![self.children.len(), self.metadata.len()];
+        let mut stack = vec![(self, 0)];
+
+        while let Some((node, next_child)) = stack.pop() {
+            if let Some(child) = node.children.get(next_child) {
+                stack.push((node, next_child + 1));
+                data.push(child.children.len());
+                data.push(child.metadata.len());
+                stack.push((child, 0));
+            } else {
+                data.extend(&node.metadata);
+            }
+        }
+
+        data
+    }
+
+    fn at_path(&self, path: &[usize]) -> Option<&Node> {
+        let mut node = self;
+        for &i in path {
+            node = node.children.get(i)?;
         }
+        Some(node)
+    }
+
+    // Walks the tree depth first with an explicit stack, calling `f` with each node's path
+    // relative to this node.
+    fn visit<F>(&self, mut f: F)
+    where
+        F: FnMut(&Node, &[usize]),
+    {
+        let mut path = Vec::new();
+        let mut stack = vec![(self, 0)];
+        f(self, &path);
+
+        while let Some((node, next_child)) = stack.pop() {
+            if let Some(child) = node.children.get(next_child) {
+                stack.push((node, next_child + 1));
+                path.push(next_child);
+                f(child, &path);
+                stack.push((child, 0));
+            } else {
+                path.pop();
+            }
+        }
+    }
+
+    // Values come from a single bottom-up pass, and the path text is extended and cut back as the
+    // walk moves rather than rebuilt, so deep trees don't cost a pass per node.
+    fn print(&self, base_path: &[usize]) -> std::io::Result<()> {
+        let mut values = HashMap::new();
+        self.compute_values(false, |node, value| {
+            values.insert(node as *const Node, value);
+        })
+        .unwrap();
+
+        let stdout = std::io::stdout();
+        let mut out = std::io::BufWriter::new(stdout.lock());
+        let mut result = Ok(());
+        let mut path_text = base_path.iter().map(|i| i.to_string()).collect::<Vec<_>>().join("/");
+        let mut ends = vec![path_text.len()];
+        let mut indent = String::new();
+
+        self.visit(|node, path| {
+            if let Some(last) = path.last() {
+                ends.truncate(path.len());
+                path_text.truncate(*ends.last().unwrap());
+                if !path_text.is_empty() {
+                    path_text.push('/');
+                }
+                path_text.push_str(&last.to_string());
+                ends.push(path_text.len());
+            }
+            if result.is_ok() {
+                while indent.len() < path.len() * 2 {
+                    indent.push_str("  ");
+                }
+                result = writeln!(
+                    out,
+                    "{}Node {}: depth {}, {} children, metadata {:?}, value {}",
+                    &indent[..path.len() * 2],
+                    if path_text.is_empty() { "root" } else { &path_text },
+                    base_path.len() + path.len(),
+                    node.children.len(),
+                    node.metadata,
+                    values[&(node as *const Node)]
+                );
+            }
+        });
+
+        result
     }
 
     fn sum_metadata(&self) -> usize {
-        self.metadata.iter().map(|m| *m).sum::<usize>()
-            + self
-                .children
-                .iter()
-                .map(|n| n.sum_metadata())
-                .sum::<usize>()
+        let mut sum = 0;
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            sum += node.metadata.iter().sum::<usize>();
+            stack.extend(&node.children);
+        }
+        sum
     }
 
-    fn value(&self) -> usize {
-        if self.children.is_empty() {
-            self.metadata.iter().map(|m| *m).sum::<usize>()
-        } else {
-            self.metadata
-                .iter()
-                .map(|m| self.children.get(*m - 1).map_or(0, |n| n.value()))
-                .sum::<usize>()
+    // Each node's value is computed once, after all of its children's, so repeated references to
+    // the same child cost nothing extra. References to children that don't exist, including 0,
+    // count as 0 unless `strict` is set, in which case they're reported.
+    fn compute_value(&self, strict: bool) -> std::result::Result<usize, ValueError> {
+        self.compute_values(strict, |_, _| {})
+    }
+
+    // As compute_value, also passing every node's value to `record` as soon as it's known.
+    fn compute_values<F>(&self, strict: bool, mut record: F) -> std::result::Result<usize, ValueError>
+    where
+        F: FnMut(&Node, usize),
+    {
+        let mut path = Vec::new();
+        let mut stack: Vec<(&Node, Vec<usize>)> = vec![(self, Vec::new())];
+
+        loop {
+            let (node, child_values) = stack.last_mut().unwrap();
+            if let Some(child) = node.children.get(child_values.len()) {
+                path.push(child_values.len());
+                stack.push((child, Vec::new()));
+                continue;
+            }
+
+            let value = if node.children.is_empty() {
+                node.metadata.iter().sum::<usize>()
+            } else {
+                let mut value = 0;
+                for (i, &m) in node.metadata.iter().enumerate() {
+                    match m.checked_sub(1).and_then(|c| child_values.get(c)) {
+                        Some(v) => value += v,
+                        None if strict => {
+                            return Err(ValueError {
+                                path,
+                                metadata_index: i,
+                                reference: m,
+                                num_children: node.children.len(),
+                            })
+                        }
+                        None => {}
+                    }
+                }
+                value
+            };
+
+            record(node, value);
+            stack.pop();
+            path.pop();
+            match stack.last_mut() {
+                Some((_, parent_values)) => parent_values.push(value),
+                None => return Ok(value),
+            }
+        }
+    }
+}
+
+fn print_stats(tree: &Node, base_path: &[usize]) {
+    let mut num_nodes = 0;
+    let mut max_depth = base_path.len();
+    let mut widest: (usize, Vec<usize>) = (tree.children.len(), base_path.to_vec());
+
+    tree.visit(|node, path| {
+        num_nodes += 1;
+        max_depth = max_depth.max(base_path.len() + path.len());
+        if node.children.len() > widest.0 {
+            widest = (node.children.len(), base_path.iter().chain(path).cloned().collect());
+        }
+    });
+
+    println!("nodes: {}", num_nodes);
+    println!("max depth: {}", max_depth);
+    println!("widest node: {} with {} children", format_path(&widest.1), widest.0);
+}
+
+// Like drop, the derived comparison would recurse once per level of the tree.
+impl PartialEq for Node {
+    fn eq(&self, other: &Node) -> bool {
+        let mut stack = vec![(self, other)];
+        while let Some((a, b)) = stack.pop() {
+            if a.metadata != b.metadata || a.children.len() != b.children.len() {
+                return false;
+            }
+            stack.extend(a.children.iter().zip(&b.children));
+        }
+        true
+    }
+}
+
+impl Eq for Node {}
+
+// The default drop would recurse once per level of the tree.
+impl Drop for Node {
+    fn drop(&mut self) {
+        let mut stack = std::mem::take(&mut self.children);
+        while let Some(mut node) = stack.pop() {
+            stack.append(&mut node.children);
         }
     }
 }
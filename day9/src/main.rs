@@ -1,17 +1,49 @@
 use lazy_static::lazy_static;
 use regex::Regex;
+use shared::CircularList;
 use std::io::prelude::*;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
 fn main() -> Result<()> {
+    let rules = parse_args()?;
     let (num_players, max_marble_value) = read_input()?;
 
-    play(num_players, max_marble_value);
+    play(num_players, max_marble_value, &rules);
 
     Ok(())
 }
 
+struct Rules {
+    divisor: u64,
+    offset: usize,
+    trace: bool,
+}
+
+fn parse_args() -> Result<Rules> {
+    let mut rules = Rules {
+        divisor: 23,
+        offset: 7,
+        trace: false,
+    };
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--divisor" => rules.divisor = args.next().ok_or("--divisor requires a number")?.parse()?,
+            "--offset" => rules.offset = args.next().ok_or("--offset requires a number")?.parse()?,
+            "--trace" => rules.trace = true,
+            _ => return Err(format!("unknown argument: {}", arg).into()),
+        }
+    }
+
+    if rules.divisor == 0 {
+        return Err("--divisor must be at least 1".into());
+    }
+
+    Ok(rules)
+}
+
 fn read_input() -> Result<(usize, u64)> {
     let mut input = String::new();
     std::io::stdin().read_to_string(&mut input)?;
@@ -29,132 +61,54 @@ fn parse_input(s: &str) -> (usize, u64) {
     (caps[1].parse().unwrap(), caps[2].parse().unwrap())
 }
 
-fn play(num_players: usize, max_marble_value: u64) {
-    let mut circle = Circle::new();
+type MarbleValue = u64;
+
+fn play(num_players: usize, max_marble_value: u64, rules: &Rules) {
+    // every special marble is kept by a player and takes another one out with it, so the circle is
+    // largest after the last marble or, when that one is special, just before it
+    let size_after = |value: u64| (1 + value).saturating_sub(2 * (value / rules.divisor));
+    let max_in_circle = size_after(max_marble_value)
+        .max(size_after(max_marble_value.saturating_sub(1)))
+        .max(1);
+    let mut circle: CircularList<MarbleValue> = CircularList::with_capacity(max_in_circle as usize);
+    circle.insert_after(0);
     let mut scores: Vec<u64> = vec![0; num_players];
     let mut current_player: usize = 0;
 
-    // println!("[-] {}", circle);
+    if rules.trace {
+        println!("[-] {}", circle);
+    }
 
     for current_marble_value in 1..=max_marble_value {
-        if current_marble_value % 23 == 0 {
+        if current_marble_value % rules.divisor == 0 {
             let score = scores.get_mut(current_player).unwrap();
             *score += current_marble_value;
-            *score += circle.remove_marble(circle.counter_clockwise(7));
+            circle.move_prev(rules.offset);
+            // with a small enough divisor the circle can run out of marbles to take
+            *score += circle.remove().unwrap_or(0);
         } else {
-            circle.insert_marble_after(circle.clockwise(1), current_marble_value);
+            circle.move_next(1);
+            circle.insert_after(current_marble_value);
+        }
+        if rules.trace {
+            println!("[{}] {}", current_player + 1, circle);
         }
-        // println!("[{}] {}", current_player + 1, circle);
         current_player = (current_player + 1) % num_players;
     }
 
-    //println!("scores: {:?}", scores);
+    let mut scoreboard: Vec<(usize, u64)> = scores.iter().cloned().enumerate().collect();
+    scoreboard.sort_by_key(|&(player, score)| (std::cmp::Reverse(score), player));
 
-    let result = scores.iter().max().unwrap();
+    let (winner, result) = scoreboard[0];
     println!("max score: {}", result);
-}
-
-struct Circle {
-    marbles: Vec<Marble>,
-    current: MarbleID,
-    first: MarbleID,
-}
-
-type MarbleID = usize;
-type MarbleValue = u64;
-
-#[derive(Clone)]
-struct Marble {
-    value: MarbleValue,
-    prev: MarbleID,
-    next: MarbleID,
-}
-
-impl Circle {
-    fn new() -> Circle {
-        let mut c = Circle {
-            marbles: Vec::new(),
-            current: 0,
-            first: 0,
-        };
-        c.add_marble(0);
-        c
-    }
-
-    fn insert_marble_after(&mut self, i: MarbleID, value: MarbleValue) -> MarbleID {
-        let after = self.marbles[i].next;
-        let new = self.add_marble(value);
-        self.marbles[new].prev = i;
-        self.marbles[new].next = after;
-        self.marbles[i].next = new;
-        self.marbles[after].prev = new;
-        self.current = new;
-        new
-    }
-
-    fn remove_marble(&mut self, i: MarbleID) -> MarbleValue {
-        let Marble { value, prev, next } = self.marbles[i];
-        self.marbles[prev].next = next;
-        self.marbles[next].prev = prev;
-        self.current = next;
-        if i == self.first {
-            self.first = self.current;
-        }
-        value
-    }
-
-    fn clockwise(&self, mut steps: usize) -> MarbleID {
-        let mut i = self.current;
-        while steps > 0 {
-            steps -= 1;
-            i = self.marbles[i].next;
-        }
-        i
-    }
-
-    fn counter_clockwise(&self, mut steps: usize) -> MarbleID {
-        let mut i = self.current;
-        while steps > 0 {
-            steps -= 1;
-            i = self.marbles[i].prev;
-        }
-        i
-    }
-
-    fn add_marble(&mut self, value: MarbleValue) -> MarbleID {
-        let i = self.marbles.len();
-        self.marbles.push(Marble::new(value));
-        i
-    }
-}
-
-impl std::fmt::Display for Circle {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let mut i = self.first;
-
-        loop {
-            let m = &self.marbles[i];
-            if i == self.current {
-                write!(f, "({})", m.value)?;
-            } else {
-                write!(f, " {} ", m.value)?;
-            }
-            i = m.next;
-            if i == self.first {
-                break;
-            }
-        }
-
-        Ok(())
-    }
-}
-
-impl Marble {
-    fn new(value: MarbleValue) -> Marble {
-        Marble {
-            value,
-            prev: 0,
-            next: 0,
-        }
+    println!("winner: player {}", winner + 1);
+    println!("scoreboard:");
+    for (player, score) in &scoreboard {
+        println!("  player {}: {}", player + 1, score);
     }
+    println!(
+        "circle: {} slots allocated, {} bytes",
+        circle.capacity(),
+        circle.memory_footprint()
+    );
 }
@@ -0,0 +1,348 @@
+use std::fmt;
+
+pub type NodeID = usize;
+
+/// A circular doubly linked list whose nodes live in a `Vec` and link to each other by index.
+/// Slots freed by removals are reused by later insertions. The list has a cursor which insertions
+/// and removals happen relative to.
+#[derive(Clone)]
+pub struct CircularList<T> {
+    nodes: Vec<Node<T>>,
+    free: Vec<NodeID>,
+    cursor: Option<NodeID>,
+    first: Option<NodeID>,
+    len: usize,
+}
+
+#[derive(Clone)]
+struct Node<T> {
+    value: Option<T>,
+    prev: NodeID,
+    next: NodeID,
+}
+
+impl<T> CircularList<T> {
+    pub fn new() -> CircularList<T> {
+        CircularList::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> CircularList<T> {
+        CircularList {
+            nodes: Vec::with_capacity(capacity),
+            free: Vec::new(),
+            cursor: None,
+            first: None,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.nodes.capacity()
+    }
+
+    /// Bytes allocated for node storage.
+    pub fn memory_footprint(&self) -> usize {
+        self.nodes.capacity() * std::mem::size_of::<Node<T>>()
+            + self.free.capacity() * std::mem::size_of::<NodeID>()
+    }
+
+    pub fn cursor(&self) -> Option<NodeID> {
+        self.cursor
+    }
+
+    /// The node iteration and display start from: the first one inserted that hasn't been removed.
+    pub fn first(&self) -> Option<NodeID> {
+        self.first
+    }
+
+    pub fn get(&self, id: NodeID) -> Option<&T> {
+        self.nodes.get(id).and_then(|n| n.value.as_ref())
+    }
+
+    pub fn current(&self) -> Option<&T> {
+        self.cursor.and_then(|id| self.get(id))
+    }
+
+    pub fn next(&self, id: NodeID) -> NodeID {
+        self.assert_live(id);
+        self.nodes[id].next
+    }
+
+    pub fn prev(&self, id: NodeID) -> NodeID {
+        self.assert_live(id);
+        self.nodes[id].prev
+    }
+
+    pub fn seek(&mut self, id: NodeID) {
+        self.assert_live(id);
+        self.cursor = Some(id);
+    }
+
+    /// Moves the cursor `steps` nodes forwards, i.e. clockwise.
+    pub fn move_next(&mut self, steps: usize) {
+        if let Some(mut id) = self.cursor {
+            for _ in 0..steps {
+                id = self.nodes[id].next;
+            }
+            self.cursor = Some(id);
+        }
+    }
+
+    /// Moves the cursor `steps` nodes backwards, i.e. counter-clockwise.
+    pub fn move_prev(&mut self, steps: usize) {
+        if let Some(mut id) = self.cursor {
+            for _ in 0..steps {
+                id = self.nodes[id].prev;
+            }
+            self.cursor = Some(id);
+        }
+    }
+
+    /// Inserts a value after the cursor and moves the cursor onto it.
+    pub fn insert_after(&mut self, value: T) -> NodeID {
+        let new = self.alloc(value);
+        match self.cursor {
+            Some(at) => {
+                let after = self.nodes[at].next;
+                self.link(at, new, after);
+            }
+            None => self.first = Some(new),
+        }
+        self.cursor = Some(new);
+        new
+    }
+
+    /// Inserts a value before the cursor and moves the cursor onto it.
+    pub fn insert_before(&mut self, value: T) -> NodeID {
+        let new = self.alloc(value);
+        match self.cursor {
+            Some(at) => {
+                let before = self.nodes[at].prev;
+                self.link(before, new, at);
+            }
+            None => self.first = Some(new),
+        }
+        self.cursor = Some(new);
+        new
+    }
+
+    /// Removes the node at the cursor, moving the cursor on to the next one.
+    pub fn remove(&mut self) -> Option<T> {
+        let id = self.cursor?;
+        let (prev, next) = (self.nodes[id].prev, self.nodes[id].next);
+
+        if next == id {
+            self.cursor = None;
+            self.first = None;
+        } else {
+            self.nodes[prev].next = next;
+            self.nodes[next].prev = prev;
+            self.cursor = Some(next);
+            if self.first == Some(id) {
+                self.first = Some(next);
+            }
+        }
+
+        self.free.push(id);
+        self.len -= 1;
+        self.nodes[id].value.take()
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.iter_from(self.first)
+    }
+
+    /// Iterates once around the list starting at the given node.
+    pub fn iter_from(&self, start: Option<NodeID>) -> Iter<'_, T> {
+        if let Some(id) = start {
+            self.assert_live(id);
+        }
+        Iter {
+            list: self,
+            start,
+            next: start,
+        }
+    }
+
+    // removed nodes keep their old links, which may point at slots that have since been reused
+    fn assert_live(&self, id: NodeID) {
+        assert!(self.get(id).is_some(), "no node {} in list", id);
+    }
+
+    fn alloc(&mut self, value: T) -> NodeID {
+        self.len += 1;
+        if let Some(id) = self.free.pop() {
+            self.nodes[id] = Node {
+                value: Some(value),
+                prev: id,
+                next: id,
+            };
+            id
+        } else {
+            let id = self.nodes.len();
+            self.nodes.push(Node {
+                value: Some(value),
+                prev: id,
+                next: id,
+            });
+            id
+        }
+    }
+
+    fn link(&mut self, before: NodeID, new: NodeID, after: NodeID) {
+        self.nodes[new].prev = before;
+        self.nodes[new].next = after;
+        self.nodes[before].next = new;
+        self.nodes[after].prev = new;
+    }
+}
+
+impl<T> Default for CircularList<T> {
+    fn default() -> CircularList<T> {
+        CircularList::new()
+    }
+}
+
+pub struct Iter<'a, T> {
+    list: &'a CircularList<T>,
+    start: Option<NodeID>,
+    next: Option<NodeID>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (NodeID, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.next?;
+        let next = self.list.nodes[id].next;
+        self.next = if Some(next) == self.start { None } else { Some(next) };
+        self.list.get(id).map(|value| (id, value))
+    }
+}
+
+/// Shows the values from the first node round, with the one at the cursor in parentheses.
+impl<T: fmt::Display> fmt::Display for CircularList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (id, value) in self.iter() {
+            if Some(id) == self.cursor {
+                write!(f, "({})", value)?;
+            } else {
+                write!(f, " {} ", value)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(list: &CircularList<u32>) -> Vec<u32> {
+        list.iter().map(|(_, &v)| v).collect()
+    }
+
+    #[test]
+    fn insert_after_and_before() {
+        let mut list = CircularList::new();
+        list.insert_after(1);
+        list.insert_after(2);
+        list.insert_before(3);
+        assert_eq!(values(&list), vec![1, 3, 2]);
+        assert_eq!(list.current(), Some(&3));
+        assert_eq!(list.len(), 3);
+    }
+
+    #[test]
+    fn move_wraps_around() {
+        let mut list = CircularList::new();
+        let first = list.insert_after(1);
+        list.insert_after(2);
+        list.insert_after(3);
+        list.move_next(1);
+        assert_eq!(list.cursor(), Some(first));
+        list.move_prev(2);
+        assert_eq!(list.current(), Some(&2));
+    }
+
+    #[test]
+    fn remove_moves_cursor_to_next() {
+        let mut list = CircularList::new();
+        let first = list.insert_after(1);
+        list.insert_after(2);
+        list.insert_after(3);
+        list.seek(first);
+        assert_eq!(list.remove(), Some(1));
+        assert_eq!(list.current(), Some(&2));
+        assert_eq!(values(&list), vec![2, 3]);
+        assert_eq!(list.remove(), Some(2));
+        assert_eq!(list.remove(), Some(3));
+        assert!(list.is_empty());
+        assert_eq!(list.remove(), None);
+        assert_eq!(list.first(), None);
+    }
+
+    #[test]
+    fn removed_slots_are_reused() {
+        let mut list = CircularList::with_capacity(3);
+        list.insert_after(1);
+        let second = list.insert_after(2);
+        list.insert_after(3);
+        list.seek(second);
+        list.remove();
+        assert_eq!(list.get(second), None);
+        assert_eq!(list.insert_after(4), second);
+        assert_eq!(values(&list), vec![1, 3, 4]);
+        assert_eq!(list.capacity(), 3);
+    }
+
+    #[test]
+    fn iter_from_any_node() {
+        let mut list = CircularList::new();
+        list.insert_after(1);
+        let second = list.insert_after(2);
+        list.insert_after(3);
+        let from: Vec<u32> = list.iter_from(Some(second)).map(|(_, &v)| v).collect();
+        assert_eq!(from, vec![2, 3, 1]);
+        assert_eq!(list.iter_from(None).count(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "no node")]
+    fn iter_from_removed_node_panics() {
+        let mut list = CircularList::new();
+        list.insert_after(1);
+        let second = list.insert_after(2);
+        list.remove();
+        list.iter_from(Some(second));
+    }
+
+    #[test]
+    #[should_panic(expected = "no node")]
+    fn next_of_removed_node_panics() {
+        let mut list = CircularList::new();
+        list.insert_after(1);
+        let second = list.insert_after(2);
+        list.remove();
+        list.next(second);
+    }
+
+    #[test]
+    fn display_marks_cursor() {
+        let mut list = CircularList::new();
+        list.insert_after(0);
+        list.insert_after(2);
+        list.insert_before(1);
+        assert_eq!(list.to_string(), " 0 (1) 2 ");
+        assert_eq!(CircularList::<u32>::new().to_string(), "");
+    }
+}
@@ -1,3 +1,6 @@
+pub mod circular_list;
+
+pub use crate::circular_list::CircularList;
 use std::convert::AsMut;
 
 #[derive(Clone)]